@@ -0,0 +1,76 @@
+#![allow(dead_code)]
+
+use std::{
+    mem, ptr,
+    time::{Duration, Instant},
+};
+
+const WARMUP: Duration = Duration::from_millis(200);
+const MEASUREMENT: Duration = Duration::from_secs(1);
+
+pub fn black_box<T>(value: T) -> T {
+    unsafe {
+        let ret = ptr::read_volatile(&value);
+        mem::forget(value);
+        ret
+    }
+}
+
+pub fn bench<T>(name: &str, mut f: impl FnMut() -> T) -> Duration {
+    let start = Instant::now();
+    while start.elapsed() < WARMUP {
+        black_box(f());
+    }
+
+    let mut iterations = 0u32;
+    let start = Instant::now();
+    while start.elapsed() < MEASUREMENT {
+        black_box(f());
+        iterations += 1;
+    }
+
+    let mean = start.elapsed() / iterations;
+    println!("{name:<40} {mean:>12.2?}/iter ({iterations} iterations)");
+    mean
+}
+
+/// Like [`bench`], but only measures `routine`, with fresh input from `setup` for each iteration.
+pub fn bench_batched<I, T>(
+    name: &str,
+    mut setup: impl FnMut() -> I,
+    mut routine: impl FnMut(I) -> T,
+) -> Duration {
+    let mut iterations = 0u32;
+    let mut elapsed = Duration::ZERO;
+
+    while iterations == 0 || elapsed < MEASUREMENT {
+        let input = setup();
+        let start = Instant::now();
+        black_box(routine(input));
+        elapsed += start.elapsed();
+        iterations += 1;
+    }
+
+    let mean = elapsed / iterations;
+    println!("{name:<40} {mean:>12.2?}/iter ({iterations} iterations)");
+    mean
+}
+
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use advent_of_code_2022::day03::{bitset, bitsets};
+use common::{bench, black_box, Rng};
+
+const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn generate_sacks(rng: &mut Rng, count: usize, max_len: u64) -> Vec<Vec<u8>> {
+    (0..count)
+        .map(|_| {
+            let len = 1 + rng.below(max_len);
+            (0..len)
+                .map(|_| ITEMS[rng.below(ITEMS.len() as u64) as usize])
+                .collect()
+        })
+        .collect()
+}
+
+fn main() {
+    let mut rng = Rng::new(2022);
+
+    for max_len in [48, 256, 4096] {
+        let sacks = generate_sacks(&mut rng, 100_000, max_len);
+        let sacks = sacks.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        bench(&format!("day03/scalar/{max_len}"), || {
+            black_box(&sacks)
+                .iter()
+                .map(|sack| bitset(sack).unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        bench(&format!("day03/vectorized/{max_len}"), || {
+            bitsets(black_box(&sacks))
+        });
+    }
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use advent_of_code_2022::day05::{Crane, Crate, CrateMover9000, CrateMover9001, Move, Stacks};
+use common::{bench_batched, Rng};
+
+const STACKS: usize = 9;
+
+fn generate_stacks(height: usize) -> Vec<Vec<Crate>> {
+    let labels = (b'A'..=b'Z')
+        .map(|c| Crate::from(char::from(c).to_string()))
+        .collect::<Vec<_>>();
+
+    (0..STACKS)
+        .map(|i| {
+            (0..height)
+                .map(|j| labels[(i + j) % labels.len()].clone())
+                .collect()
+        })
+        .collect()
+}
+
+fn generate_moves(rng: &mut Rng, height: usize, count: usize) -> Vec<Move> {
+    let mut lens = vec![height; STACKS];
+
+    (0..count)
+        .map(|_| {
+            let from = (0..STACKS).max_by_key(|&i| lens[i]).unwrap();
+            let to = (from + 1 + rng.below(STACKS as u64 - 1) as usize) % STACKS;
+            let count = 1 + rng.below(lens[from] as u64) as usize;
+            lens[from] -= count;
+            lens[to] += count;
+            Move { count, from, to }
+        })
+        .collect()
+}
+
+// Interleaves single crates from two stacks onto a third, then moves it back and forth whole
+// between the first two
+fn generate_interleaved_moves(height: usize, count: usize) -> Vec<Move> {
+    let interleave = (0..height).flat_map(|_| {
+        [
+            Move {
+                count: 1,
+                from: 1,
+                to: 0,
+            },
+            Move {
+                count: 1,
+                from: 2,
+                to: 0,
+            },
+        ]
+    });
+
+    let back_and_forth = (0..count).map(|i| Move {
+        count: 3 * height,
+        from: i % 2,
+        to: (i + 1) % 2,
+    });
+
+    interleave.chain(back_and_forth).collect()
+}
+
+// The previous implementation, moving one crate at a time
+fn move_one_by_one(stacks: &mut [Vec<Crate>], moves: &[Move], retain_order: bool) {
+    for &Move { count, from, to } in moves {
+        for _ in 0..count {
+            let c = stacks[from].pop().unwrap();
+            stacks[to].push(c);
+        }
+
+        if retain_order {
+            let len = stacks[to].len();
+            stacks[to][len - count..].reverse()
+        }
+    }
+}
+
+fn move_rope(stacks: Vec<Vec<Crate>>, moves: &[Move], crane: &impl Crane) -> Stacks {
+    let mut stacks = Stacks::from(stacks);
+    for &instr in moves {
+        stacks.apply(crane, instr).unwrap();
+    }
+    stacks
+}
+
+fn main() {
+    let mut rng = Rng::new(2022);
+
+    let random = [10_000, 1_000_000]
+        .map(|height| ("random", height, generate_moves(&mut rng, height, 1_000)));
+    let interleaved = [20_000, 80_000].map(|height| {
+        (
+            "interleaved",
+            height,
+            generate_interleaved_moves(height, 100),
+        )
+    });
+
+    for (kind, height, moves) in random.into_iter().chain(interleaved) {
+        bench_batched(
+            &format!("day05/9000/one-by-one/{kind}/{height}"),
+            || generate_stacks(height),
+            |mut stacks| move_one_by_one(&mut stacks, &moves, false),
+        );
+
+        bench_batched(
+            &format!("day05/9000/rope/{kind}/{height}"),
+            || generate_stacks(height),
+            |stacks| move_rope(stacks, &moves, &CrateMover9000),
+        );
+
+        bench_batched(
+            &format!("day05/9001/one-by-one/{kind}/{height}"),
+            || generate_stacks(height),
+            |mut stacks| move_one_by_one(&mut stacks, &moves, true),
+        );
+
+        bench_batched(
+            &format!("day05/9001/rope/{kind}/{height}"),
+            || generate_stacks(height),
+            |stacks| move_rope(stacks, &moves, &CrateMover9001),
+        );
+    }
+}
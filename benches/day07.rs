@@ -0,0 +1,78 @@
+mod common;
+
+use std::fmt::Write;
+
+use advent_of_code_2022::day07::{parse_input, Filesystem};
+use common::{bench, black_box, Rng};
+
+const DIRECTORIES: usize = 100_000;
+
+// Generates a transcript exploring a random tree, where each new directory is placed
+// under one of the `spread` most recently created ones
+fn generate_transcript(rng: &mut Rng, spread: usize) -> String {
+    let mut children = vec![Vec::new(); DIRECTORIES];
+
+    for child in 1..DIRECTORIES {
+        let parent = child - 1 - rng.below(spread.min(child) as u64) as usize;
+        children[parent].push(child);
+    }
+
+    let mut transcript = String::from("$ cd /\n");
+    let mut stack = vec![Some(0)];
+
+    while let Some(entry) = stack.pop() {
+        let Some(dir) = entry else {
+            transcript.push_str("$ cd ..\n");
+            continue;
+        };
+
+        if dir != 0 {
+            writeln!(transcript, "$ cd d{dir}").unwrap();
+            stack.push(None);
+        }
+
+        transcript.push_str("$ ls\n");
+        for &child in &children[dir] {
+            writeln!(transcript, "dir d{child}").unwrap();
+        }
+        for file in 0..rng.below(4) {
+            writeln!(transcript, "{} f{file}", 1 + rng.below(100_000)).unwrap();
+        }
+
+        stack.extend(children[dir].iter().rev().map(|&child| Some(child)));
+    }
+
+    transcript
+}
+
+// The previous approach, recomputing the size of every directory from scratch
+fn sizes_per_node(fs: &Filesystem) -> Vec<u32> {
+    let mut sizes = Vec::new();
+    let mut nodes = vec![fs];
+
+    while let Some(node) = nodes.pop() {
+        if let Some(entries) = node.list() {
+            sizes.push(node.size());
+            nodes.extend(entries.map(|(_, node)| node));
+        }
+    }
+
+    sizes
+}
+
+fn main() {
+    let mut rng = Rng::new(2022);
+
+    for (shape, spread) in [("wide", DIRECTORIES), ("deep", 100)] {
+        let transcript = generate_transcript(&mut rng, spread);
+        let fs = parse_input(&transcript);
+
+        bench(&format!("day07/{shape}/per-node"), || {
+            sizes_per_node(black_box(&fs))
+        });
+
+        bench(&format!("day07/{shape}/single-pass"), || {
+            black_box(&fs).directory_sizes()
+        });
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use advent_of_code_2022::day08::{
+    count_visible, count_visible_naive, max_scenic_score, max_scenic_score_naive, Grid,
+};
+use common::{bench, black_box, Rng};
+
+// Heights chosen uniformly at random, where most rays are blocked after a few trees
+fn random_forest(rng: &mut Rng, size: usize) -> String {
+    let mut forest = String::new();
+
+    for _ in 0..size {
+        for _ in 0..size {
+            forest.push(char::from(b'0' + rng.below(10) as u8));
+        }
+        forest.push('\n');
+    }
+
+    forest
+}
+
+// Heights rising towards the center, where most rays towards the edge run all the way to it
+fn ramp_forest(size: usize) -> String {
+    let mut forest = String::new();
+
+    for y in 0..size {
+        for x in 0..size {
+            let depth = x.min(y).min(size - 1 - x).min(size - 1 - y);
+            let height = (depth * 20 / size).min(9);
+            forest.push(char::from(b'0' + height as u8));
+        }
+        forest.push('\n');
+    }
+
+    forest
+}
+
+// Heights counting up along both axes, so trees see past every shorter tree behind them. Views are
+// blocked by the next tree at least as tall, so with only ten heights this is about as far as the
+// naive scenic walk can be made to go, and where the linear scan gains the most over it.
+fn sawtooth_forest(size: usize) -> String {
+    let mut forest = String::new();
+
+    for y in 0..size {
+        for x in 0..size {
+            forest.push(char::from(b'0' + ((x + y) % 10) as u8));
+        }
+        forest.push('\n');
+    }
+
+    forest
+}
+
+fn main() {
+    let mut rng = Rng::new(2022);
+
+    for size in [100, 1000] {
+        let forests = [
+            ("random", random_forest(&mut rng, size)),
+            ("ramp", ramp_forest(size)),
+            ("sawtooth", sawtooth_forest(size)),
+        ];
+
+        for (shape, forest) in forests {
+            let grid: Grid = forest.parse().unwrap();
+            let name = format!("day08/{shape}/{size}");
+
+            bench(&format!("{name}/visible/naive"), || {
+                count_visible_naive(black_box(&grid))
+            });
+            bench(&format!("{name}/visible/linear"), || {
+                count_visible(black_box(&grid))
+            });
+            bench(&format!("{name}/scenic/naive"), || {
+                max_scenic_score_naive(black_box(&grid))
+            });
+            bench(&format!("{name}/scenic/linear"), || {
+                max_scenic_score(black_box(&grid))
+            });
+        }
+    }
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use std::collections::HashSet;
+
+use advent_of_code_2022::day09::{parse_input, Bitmap, Motion, Rope};
+use common::{bench, black_box, Rng};
+
+const STEPS: u32 = 10_000_000;
+
+// Generates random motions in the four main directions, adding up to `STEPS` steps
+fn generate_motions(rng: &mut Rng) -> Vec<Motion> {
+    let mut motions = Vec::new();
+    let mut remaining = STEPS;
+
+    while remaining > 0 {
+        let delta = [[1, 0], [-1, 0], [0, 1], [0, -1]][rng.below(4) as usize];
+        let steps = (1 + rng.below(20) as u32).min(remaining);
+        motions.push(Motion { delta, steps });
+        remaining -= steps;
+    }
+
+    motions
+}
+
+fn main() {
+    let mut rng = Rng::new(2022);
+
+    let inputs = [
+        (
+            "input",
+            parse_input(include_str!("../src/day09/input.txt")).collect(),
+        ),
+        ("generated", generate_motions(&mut rng)),
+    ];
+
+    for (name, motions) in inputs {
+        for len in [2, 10] {
+            bench(&format!("day09/{name}/{len}/hash-set"), || {
+                let mut visited = HashSet::new();
+                Rope::new(len).visit(len - 1, black_box(&motions).iter().copied(), &mut visited);
+                visited.len()
+            });
+
+            bench(&format!("day09/{name}/{len}/bitmap"), || {
+                let mut visited = Bitmap::new();
+                Rope::new(len).visit(len - 1, black_box(&motions).iter().copied(), &mut visited);
+                visited.len()
+            });
+        }
+    }
+}
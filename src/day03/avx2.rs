@@ -0,0 +1,87 @@
+use std::{arch::x86_64::*, mem};
+
+const LANES: usize = 16;
+
+// Each sack is vectorized on its own, 16 items at a time, which already fills every lane
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn bitsets(sacks: &[&[u8]]) -> Result<Vec<u64>, usize> {
+    sacks
+        .iter()
+        .enumerate()
+        .map(|(index, sack)| bitset(sack).ok_or(index))
+        .collect()
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn bitset(sack: &[u8]) -> Option<u64> {
+    let mut set = _mm256_setzero_si256();
+    let mut invalid = _mm_setzero_si128();
+
+    let mut chunks = sack.chunks_exact(LANES);
+
+    for chunk in &mut chunks {
+        let items = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+        invalid = _mm_or_si128(invalid, non_letters(items));
+        set = insert(set, priorities(items));
+    }
+
+    let remainder = chunks.remainder();
+
+    if let Some(&first) = remainder.first() {
+        // Repeating an item doesn't change the set
+        let mut chunk = [first; LANES];
+        chunk[..remainder.len()].copy_from_slice(remainder);
+        let items = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+        invalid = _mm_or_si128(invalid, non_letters(items));
+        set = insert(set, priorities(items));
+    }
+
+    if _mm_movemask_epi8(invalid) != 0 {
+        return None;
+    }
+
+    let [a, b, c, d]: [u64; 4] = mem::transmute(set);
+
+    Some(a | b | c | d)
+}
+
+// Sets every byte which isn't an ASCII letter
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn non_letters(items: __m128i) -> __m128i {
+    // Setting the case bit maps the letters, and only the letters, onto the lowercase ones
+    let lowercase = _mm_or_si128(items, _mm_set1_epi8(0x20));
+    _mm_or_si128(
+        _mm_cmpgt_epi8(_mm_set1_epi8(b'a' as i8), lowercase),
+        _mm_cmpgt_epi8(lowercase, _mm_set1_epi8(b'z' as i8)),
+    )
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn priorities(items: __m128i) -> __m128i {
+    let is_lowercase = _mm_cmpgt_epi8(items, _mm_set1_epi8(b'Z' as i8));
+    let offset = _mm_add_epi8(
+        _mm_set1_epi8((b'A' - 27) as i8),
+        _mm_and_si128(is_lowercase, _mm_set1_epi8((b'a' - b'A' + 26) as i8)),
+    );
+    _mm_sub_epi8(items, offset)
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn insert(set: __m256i, priorities: __m128i) -> __m256i {
+    let set = insert_lanes(set, priorities);
+    let set = insert_lanes(set, _mm_srli_si128::<4>(priorities));
+    let set = insert_lanes(set, _mm_srli_si128::<8>(priorities));
+    insert_lanes(set, _mm_srli_si128::<12>(priorities))
+}
+
+// Inserts the priorities in the lowest four bytes, one in each 64-bit lane
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn insert_lanes(set: __m256i, priorities: __m128i) -> __m256i {
+    let items = _mm256_sllv_epi64(_mm256_set1_epi64x(1), _mm256_cvtepu8_epi64(priorities));
+    _mm256_or_si256(set, items)
+}
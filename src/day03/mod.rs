@@ -1,46 +1,68 @@
 use itertools::Itertools;
 
-const fn priority(item: u8) -> u8 {
+#[cfg(target_arch = "x86_64")]
+mod avx2;
+
+const fn priority(item: u8) -> Option<u8> {
     match item {
-        b'a'..=b'z' => item - b'a' + 1,
-        b'A'..=b'Z' => item - b'A' + 27,
-        _ => unreachable!(),
+        b'a'..=b'z' => Some(item - b'a' + 1),
+        b'A'..=b'Z' => Some(item - b'A' + 27),
+        _ => None,
     }
 }
 
-fn bitset(sack: &[u8]) -> u64 {
-    sack.iter().fold(0, |set, &item| set | 1 << priority(item))
+/// The set of item priorities in a sack, or `None` if it holds something other than a letter.
+pub fn bitset(sack: &[u8]) -> Option<u64> {
+    sack.iter()
+        .try_fold(0, |set, &item| Some(set | 1 << priority(item)?))
+}
+
+/// The bitsets of many sacks, each vectorized on its own when AVX2 is available. Fails with the
+/// index of the first sack holding something other than a letter.
+pub fn bitsets(sacks: &[&[u8]]) -> Result<Vec<u64>, usize> {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        return unsafe { avx2::bitsets(sacks) };
+    }
+
+    sacks
+        .iter()
+        .enumerate()
+        .map(|(index, sack)| bitset(sack).ok_or(index))
+        .collect()
 }
 
 pub fn part1(input: &str) -> u32 {
-    input
+    let sacks = input
         .trim()
         .lines()
-        .map(|line| {
+        .flat_map(|line| {
             let line = line.as_bytes();
             let (a, b) = line.split_at(line.len() / 2);
-
-            let a = bitset(a);
-            let b = bitset(b);
-
-            (a & b).trailing_zeros()
+            [a, b]
         })
+        .collect::<Vec<_>>();
+
+    bitsets(&sacks)
+        .unwrap()
+        .into_iter()
+        .tuples()
+        .map(|(a, b)| (a & b).trailing_zeros())
         .sum()
 }
 
 pub fn part2(input: &str) -> u32 {
-    input
+    let sacks = input
         .trim()
         .lines()
         .map(|line| line.as_bytes())
-        .tuples()
-        .map(|(a, b, c)| {
-            let a = bitset(a);
-            let b = bitset(b);
-            let c = bitset(c);
+        .collect::<Vec<_>>();
 
-            (a & b & c).trailing_zeros()
-        })
+    bitsets(&sacks)
+        .unwrap()
+        .into_iter()
+        .tuples()
+        .map(|(a, b, c)| (a & b & c).trailing_zeros())
         .sum()
 }
 
@@ -62,4 +84,35 @@ mod tests {
         assert_eq!(part2(INPUT_TEST), 70);
         assert_eq!(part2(INPUT), 2821);
     }
+
+    #[test]
+    fn bitsets_works() {
+        let sacks = INPUT
+            .lines()
+            .chain([
+                "",
+                "a",
+                "Z",
+                "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ",
+            ])
+            .map(|line| line.as_bytes())
+            .collect::<Vec<_>>();
+
+        let expected = sacks.iter().map(|sack| bitset(sack).unwrap());
+        let expected = expected.collect::<Vec<_>>();
+
+        for len in 0..sacks.len().min(9) {
+            assert_eq!(bitsets(&sacks[..len]), Ok(expected[..len].to_vec()));
+        }
+        assert_eq!(bitsets(&sacks), Ok(expected));
+
+        for invalid in [b'0', b'\r', b'`', b'{', b'@', b'[', 0x80 | b'a'] {
+            assert_eq!(bitset(&[b'a', invalid]), None);
+
+            let mut sack = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ".to_vec();
+            sack[37] = invalid;
+            let sacks = [&b"a"[..], b"", b"Zz", b"xyz", b"q", &sack, b"b"];
+            assert_eq!(bitsets(&sacks), Err(5), "{invalid}");
+        }
+    }
 }
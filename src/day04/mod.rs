@@ -1,45 +1,147 @@
 use std::array;
-use std::ops::RangeInclusive;
-use std::str::FromStr;
 
-struct Range(RangeInclusive<u8>);
+use itertools::Itertools;
 
-impl Range {
-    fn contains(&self, other: &Self) -> bool {
-        self.0.contains(other.0.start()) && self.0.contains(other.0.end())
-    }
-
-    fn overlaps(&self, other: &Self) -> bool {
-        self.0.contains(other.0.start()) || self.0.contains(other.0.end())
-    }
-}
+use crate::interval::Interval;
 
-impl FromStr for Range {
-    type Err = ();
+type Section = u64;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (a, b) = s.split_once('-').unwrap();
-        Ok(Self(a.parse().unwrap()..=b.parse().unwrap()))
-    }
+fn parse_range(s: &str) -> Interval<Section> {
+    let (a, b) = s.split_once('-').unwrap();
+    Interval::new(a.parse().unwrap(), b.parse().unwrap()).unwrap()
 }
 
-fn parse_ranges<const N: usize>(input: &str) -> impl Iterator<Item = [Range; N]> + '_ {
+fn parse_ranges<const N: usize>(input: &str) -> impl Iterator<Item = [Interval<Section>; N]> + '_ {
     input.trim().lines().map(|line| {
         let mut ranges = line.split(',');
-        array::from_fn(|_| ranges.next().unwrap().parse().unwrap())
+        array::from_fn(|_| parse_range(ranges.next().unwrap()))
     })
 }
 
+pub fn parse_assignments(input: &str) -> impl Iterator<Item = Vec<Interval<Section>>> + '_ {
+    input
+        .trim()
+        .lines()
+        .map(|line| line.split(',').map(parse_range).collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    Contains,
+    ContainedBy,
+    Overlaps,
+}
+
+impl Relation {
+    fn between(a: &Interval<Section>, b: &Interval<Section>) -> Option<Self> {
+        if a.contains_interval(b) {
+            Some(Self::Contains)
+        } else if b.contains_interval(a) {
+            Some(Self::ContainedBy)
+        } else if a.overlaps(b) {
+            Some(Self::Overlaps)
+        } else {
+            None
+        }
+    }
+}
+
+/// Every pair `(i, j)` with `i < j` of assignments on a line that overlap, and how.
+pub fn related_pairs(
+    assignments: &[Interval<Section>],
+) -> impl Iterator<Item = (usize, usize, Relation)> + '_ {
+    (0..assignments.len())
+        .tuple_combinations()
+        .filter_map(|(i, j)| Some((i, j, Relation::between(&assignments[i], &assignments[j])?)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Elf {
+    pub line: usize,
+    pub index: usize,
+    pub sections: Interval<Section>,
+}
+
+/// Which elves have overlapping sections, across all lines of the input.
+#[derive(Debug)]
+pub struct OverlapGraph {
+    elves: Vec<Elf>,
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl OverlapGraph {
+    pub fn new(input: &str) -> Self {
+        let elves = parse_assignments(input)
+            .enumerate()
+            .flat_map(|(line, assignments)| {
+                assignments
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(index, sections)| Elf {
+                        line,
+                        index,
+                        sections,
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let mut order = (0..elves.len()).collect::<Vec<_>>();
+        order.sort_unstable_by_key(|&i| elves[i].sections.start());
+
+        let mut neighbors = vec![Vec::new(); elves.len()];
+
+        for (n, &i) in order.iter().enumerate() {
+            let end = elves[i].sections.end();
+            for &j in order[n + 1..]
+                .iter()
+                .take_while(|&&j| elves[j].sections.start() <= end)
+            {
+                neighbors[i].push(j);
+                neighbors[j].push(i);
+            }
+        }
+
+        for list in &mut neighbors {
+            list.sort_unstable();
+        }
+
+        Self { elves, neighbors }
+    }
+
+    pub fn elves(&self) -> &[Elf] {
+        &self.elves
+    }
+
+    pub fn neighbors(&self, elf: usize) -> &[usize] {
+        &self.neighbors[elf]
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbors.iter().enumerate().flat_map(|(i, list)| {
+            list.iter()
+                .copied()
+                .filter(move |&j| i < j)
+                .map(move |j| (i, j))
+        })
+    }
+
+    /// Elves whose sections overlap with those of any other elf.
+    pub fn overlapping(&self) -> impl Iterator<Item = &Elf> + '_ {
+        self.elves
+            .iter()
+            .zip(&self.neighbors)
+            .filter_map(|(elf, list)| (!list.is_empty()).then_some(elf))
+    }
+}
+
 pub fn part1(input: &str) -> usize {
     parse_ranges(input)
-        .filter(|[a, b]| a.contains(b) || b.contains(a))
+        .filter(|[a, b]| a.contains_interval(b) || b.contains_interval(a))
         .count()
 }
 
 pub fn part2(input: &str) -> usize {
-    parse_ranges(input)
-        .filter(|[a, b]| a.overlaps(b) || b.overlaps(a))
-        .count()
+    parse_ranges(input).filter(|[a, b]| a.overlaps(b)).count()
 }
 
 #[cfg(test)]
@@ -60,4 +162,43 @@ mod tests {
         assert_eq!(part2(INPUT_TEST), 4);
         assert_eq!(part2(INPUT), 928);
     }
+
+    #[test]
+    fn related_pairs_works() {
+        let assignments = parse_assignments("2-8,3-7,7-9,10-12").next().unwrap();
+
+        assert_eq!(
+            related_pairs(&assignments).collect::<Vec<_>>(),
+            [
+                (0, 1, Relation::Contains),
+                (0, 2, Relation::Overlaps),
+                (1, 2, Relation::Overlaps)
+            ]
+        );
+    }
+
+    #[test]
+    fn overlap_graph_works() {
+        let graph = OverlapGraph::new(INPUT);
+        let elves = graph.elves();
+
+        let expected = (0..elves.len())
+            .tuple_combinations()
+            .filter(|&(i, j)| elves[i].sections.overlaps(&elves[j].sections))
+            .collect::<Vec<_>>();
+
+        assert_eq!(graph.edges().sorted().collect::<Vec<_>>(), expected);
+
+        let graph = OverlapGraph::new("1-2,4-5\n3-3,5-6\n8-9");
+        let overlapping = graph.overlapping().map(|elf| (elf.line, elf.index));
+
+        assert_eq!(overlapping.collect::<Vec<_>>(), [(0, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn large_sections_work() {
+        let input = "1000-5000000000,4000-9000\n300-70000,70000-80000";
+        assert_eq!(part1(input), 1);
+        assert_eq!(part2(input), 2);
+    }
 }
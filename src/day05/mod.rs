@@ -1,68 +1,305 @@
+use std::{collections::HashMap, fmt::Display, rc::Rc, str::FromStr};
+
 use itertools::Itertools;
 
-fn parse_input(
-    input: &str,
-) -> (
-    Box<[Vec<char>]>,
-    impl Iterator<Item = (usize, usize, usize)> + '_,
-) {
-    let (stack, instructions) = input.split_once("\n\n").unwrap();
+mod stack;
 
-    let mut stacks: Vec<Vec<char>> = Vec::with_capacity(9);
+pub use stack::Stack;
 
-    for row in stack.lines().rev().skip(1) {
-        for (i, c) in row.chars().skip(1).step_by(4).enumerate() {
-            if stacks.len() <= i {
-                stacks.resize_with(i + 1, Default::default);
-            }
+/// A crate label, shared between all crates with the same label.
+pub type Crate = Rc<str>;
 
-            if c.is_ascii_uppercase() {
-                stacks[i].push(c)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stacks(Box<[Stack]>);
+
+impl Stacks {
+    pub fn get(&self, index: usize) -> Option<&Stack> {
+        self.0.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn tops(&self) -> String {
+        self.0
+            .iter()
+            .filter_map(|stack| stack.last())
+            .map(|c| &**c)
+            .collect()
+    }
+
+    pub fn apply(&mut self, crane: &impl Crane, instr: Move) -> Result<(), MoveError> {
+        let Move { count, from, to } = instr;
+
+        for index in [from, to] {
+            if index >= self.len() {
+                return Err(MoveError::UnknownStack(index));
             }
         }
+
+        if from == to {
+            return Err(MoveError::SameStack(from));
+        }
+
+        let available = self.0[from].len();
+
+        if available < count {
+            return Err(MoveError::NotEnoughCrates {
+                stack: from,
+                available,
+                requested: count,
+            });
+        }
+
+        let (from, to) = if from < to {
+            let (head, tail) = self.0.split_at_mut(to);
+            (&mut head[from], &mut tail[0])
+        } else {
+            let (head, tail) = self.0.split_at_mut(from);
+            (&mut tail[0], &mut head[to])
+        };
+
+        crane.move_crates(from, to, count);
+
+        Ok(())
     }
+}
 
-    (
-        stacks.into(),
-        instructions.lines().map(|instr| {
-            instr
-                .split_whitespace()
-                .filter_map(|s| s.parse().ok())
-                .tuples()
-                .map(|(count, from, to)| (count, from - 1, to - 1))
-                .exactly_one()
-                .unwrap()
-        }),
-    )
+impl From<Vec<Vec<Crate>>> for Stacks {
+    fn from(stacks: Vec<Vec<Crate>>) -> Self {
+        Self(stacks.into_iter().map(Stack::from).collect())
+    }
 }
 
-pub fn part1(input: &str) -> String {
-    let (mut stacks, instructions) = parse_input(input);
+// Splits a line on spaces, keeping the char offset of each token
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> + '_ {
+    line.split(' ')
+        .scan(0, |offset, token| {
+            let start = *offset;
+            *offset += token.chars().count() + 1;
+            Some((start, token))
+        })
+        .filter(|(_, token)| !token.is_empty())
+}
+
+// Twice the center of a token, to keep it integral
+fn center(start: usize, token: &str) -> usize {
+    2 * start + token.chars().count()
+}
+
+impl FromStr for Stacks {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rows = s.lines().rev();
+
+        let mut centers = Vec::new();
 
-    for (count, from, to) in instructions {
-        for _ in 0..count {
-            let c = stacks[from].pop().unwrap();
-            stacks[to].push(c);
+        for (start, token) in tokens(rows.next().ok_or(())?) {
+            if token.parse() != Ok(centers.len() + 1) {
+                return Err(());
+            }
+            centers.push(center(start, token));
         }
+
+        let mut stacks: Vec<Vec<Crate>> = vec![Vec::new(); centers.len()];
+        let mut labels = HashMap::<&str, Crate>::new();
+
+        for row in rows {
+            for (start, token) in tokens(row) {
+                let label = token
+                    .strip_prefix('[')
+                    .and_then(|token| token.strip_suffix(']'))
+                    .filter(|label| !label.is_empty())
+                    .ok_or(())?;
+
+                let center = center(start, token);
+                let index = (0..centers.len())
+                    .min_by_key(|&i| centers[i].abs_diff(center))
+                    .ok_or(())?;
+
+                let label = labels.entry(label).or_insert_with(|| label.into());
+                stacks[index].push(label.clone());
+            }
+        }
+
+        Ok(stacks.into())
     }
+}
 
-    stacks.iter().map(|s| *s.last().unwrap()).collect()
+impl Display for Stacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stacks = self
+            .0
+            .iter()
+            .map(|stack| stack.iter().collect())
+            .collect::<Vec<Vec<_>>>();
+
+        let label_width = stacks.iter().flatten().map(|c| c.chars().count());
+        let width = label_width
+            .map(|len| len + 2)
+            .chain([self.len().to_string().len()])
+            .max()
+            .unwrap();
+
+        let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+
+        for row in (0..height).rev() {
+            for (i, stack) in stacks.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                match stack.get(row) {
+                    Some(c) => write!(f, "{:^width$}", format!("[{c}]"))?,
+                    None => write!(f, "{:width$}", "")?,
+                }
+            }
+            writeln!(f)?;
+        }
+
+        for i in 0..self.len() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:^width$}", i + 1)?;
+        }
+
+        Ok(())
+    }
 }
 
-pub fn part2(input: &str) -> String {
-    let (mut stacks, instructions) = parse_input(input);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub count: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl FromStr for Move {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_whitespace()
+            .filter_map(|s| s.parse().ok())
+            .tuples()
+            .exactly_one()
+            .ok()
+            .and_then(|(count, from, to): (usize, usize, usize)| {
+                Some(Move {
+                    count,
+                    from: from.checked_sub(1)?,
+                    to: to.checked_sub(1)?,
+                })
+            })
+            .ok_or(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    UnknownStack(usize),
+    SameStack(usize),
+    NotEnoughCrates {
+        stack: usize,
+        available: usize,
+        requested: usize,
+    },
+}
+
+pub trait Crane {
+    /// Moves the top `count` crates of `from`, which has at least `count` crates, onto `to`.
+    fn move_crates(&self, from: &mut Stack, to: &mut Stack, count: usize);
+}
+
+/// Moves one crate at a time.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn move_crates(&self, from: &mut Stack, to: &mut Stack, count: usize) {
+        let mut crates = from.split_off(count);
+        crates.reverse();
+        to.append(crates);
+    }
+}
+
+/// Moves multiple crates at once, retaining their order.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn move_crates(&self, from: &mut Stack, to: &mut Stack, count: usize) {
+        to.append(from.split_off(count));
+    }
+}
 
-    for (count, from, to) in instructions {
-        for _ in 0..count {
-            let c = stacks[from].pop().unwrap();
-            stacks[to].push(c);
+/// Applies the moves one at a time, yielding the state of the stacks after each one.
+pub struct Replay<C, I> {
+    crane: C,
+    stacks: Stacks,
+    moves: I,
+    failed: bool,
+}
+
+impl<C: Crane, I: Iterator<Item = Move>> Replay<C, I> {
+    pub fn new(crane: C, stacks: Stacks, moves: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            crane,
+            stacks,
+            moves: moves.into_iter(),
+            failed: false,
         }
+    }
+}
 
-        let len = stacks[to].len();
-        stacks[to][len - count..].reverse()
+impl<C: Crane, I: Iterator<Item = Move>> Iterator for Replay<C, I> {
+    type Item = Result<Stacks, MoveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let instr = self.moves.next()?;
+
+        match self.stacks.apply(&self.crane, instr) {
+            Ok(()) => Some(Ok(self.stacks.clone())),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
     }
+}
 
-    stacks.iter().map(|s| *s.last().unwrap()).collect()
+pub fn parse_input(input: &str) -> (Stacks, impl Iterator<Item = Move> + '_) {
+    let (stack, instructions) = input.split_once("\n\n").unwrap();
+
+    (
+        stack.parse().unwrap(),
+        instructions.lines().map(|instr| instr.parse().unwrap()),
+    )
+}
+
+fn solve(input: &str, crane: impl Crane) -> Result<String, MoveError> {
+    let (mut stacks, instructions) = parse_input(input);
+
+    for instr in instructions {
+        stacks.apply(&crane, instr)?;
+    }
+
+    Ok(stacks.tops())
+}
+
+pub fn part1(input: &str) -> String {
+    solve(input, CrateMover9000).unwrap()
+}
+
+pub fn part2(input: &str) -> String {
+    solve(input, CrateMover9001).unwrap()
 }
 
 #[cfg(test)]
@@ -83,4 +320,129 @@ mod tests {
         assert_eq!(part2(INPUT_TEST), "MCD");
         assert_eq!(part2(INPUT), "JSDHQMZGF");
     }
+
+    #[test]
+    fn render_works() {
+        for input in [INPUT_TEST, INPUT] {
+            let (drawing, _) = input.split_once("\n\n").unwrap();
+            let (stacks, _) = parse_input(input);
+
+            assert_eq!(stacks.to_string(), drawing);
+            assert_eq!(stacks.to_string().parse(), Ok(stacks));
+        }
+    }
+
+    #[test]
+    fn render_large_works() {
+        let drawing = concat!(
+            "[AB]                                         [C] \n",
+            "[X]  [Y]  [Y]  [Y]  [Y]  [Y]  [Y]  [Y]  [Y]  [Z] \n",
+            " 1    2    3    4    5    6    7    8    9    10 ",
+        );
+
+        let stacks: Stacks = drawing.parse().unwrap();
+
+        assert_eq!(stacks.len(), 10);
+        assert_eq!(stacks.tops(), "ABYYYYYYYYC");
+        assert_eq!(stacks.to_string(), drawing);
+
+        let labels = (1..9).map(|i| stacks.get(i).unwrap().get(0).unwrap());
+        assert!(labels.tuple_windows().all(|(a, b)| Rc::ptr_eq(a, b)));
+    }
+
+    #[test]
+    fn replay_works() {
+        let (stacks, instructions) = parse_input(INPUT_TEST);
+
+        let tops = Replay::new(CrateMover9001, stacks, instructions)
+            .map(|stacks| stacks.unwrap().tops())
+            .collect::<Vec<_>>();
+
+        assert_eq!(tops, ["DCP", "CD", "CD", "MCD"]);
+    }
+
+    #[test]
+    fn invalid_moves_fail() {
+        let (stacks, _) = parse_input(INPUT_TEST);
+
+        let moves = [
+            "move 2 from 2 to 1",
+            "move 2 from 2 to 1",
+            "move 1 from 1 to 3",
+        ];
+        let moves = moves.map(|instr| instr.parse().unwrap());
+
+        let results = Replay::new(CrateMover9000, stacks.clone(), moves).collect::<Vec<_>>();
+
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1],
+            Err(MoveError::NotEnoughCrates {
+                stack: 1,
+                available: 1,
+                requested: 2
+            })
+        );
+        assert_eq!(results.len(), 2);
+
+        let mut stacks = stacks;
+        let instr = "move 1 from 1 to 4".parse().unwrap();
+        assert_eq!(
+            stacks.apply(&CrateMover9000, instr),
+            Err(MoveError::UnknownStack(3))
+        );
+        let instr = "move 1 from 1 to 1".parse().unwrap();
+        assert_eq!(
+            stacks.apply(&CrateMover9000, instr),
+            Err(MoveError::SameStack(0))
+        );
+    }
+
+    #[test]
+    fn stacks_match_vectors() {
+        let mut state = 2022u64;
+        let mut random = |n: usize| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1);
+            (state >> 33) as usize % n
+        };
+
+        let labels = (0..100).map(|i| Crate::from(i.to_string()));
+        let labels = labels.collect::<Vec<_>>();
+
+        for retain_order in [false, true] {
+            let mut expected = (0..5)
+                .map(|i| labels[i * 20..i * 20 + random(20)].to_vec())
+                .collect::<Vec<_>>();
+            let mut stacks = Stacks::from(expected.clone());
+
+            for _ in 0..2000 {
+                let from = random(5);
+                let to = (from + 1 + random(4)) % 5;
+                let count = random(expected[from].len() + 1);
+
+                let len = expected[from].len();
+                let mut crates = expected[from].split_off(len - count);
+                if !retain_order {
+                    crates.reverse();
+                }
+                expected[to].extend(crates);
+
+                let instr = Move { count, from, to };
+                if retain_order {
+                    stacks.apply(&CrateMover9001, instr).unwrap();
+                } else {
+                    stacks.apply(&CrateMover9000, instr).unwrap();
+                }
+
+                for (stack, expected) in stacks.0.iter().zip(&expected) {
+                    assert_eq!(stack.len(), expected.len());
+                    assert_eq!(stack.last(), expected.last());
+                    assert!(stack.iter().eq(expected));
+                    assert!((0..=expected.len()).all(|i| stack.get(i) == expected.get(i)));
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,291 @@
+use std::{cell::Cell, ops::Range, rc::Rc};
+
+use itertools::Either;
+
+use super::Crate;
+
+/// A shared, possibly reversed, slice of crates.
+#[derive(Debug, Clone)]
+struct Segment {
+    crates: Rc<[Crate]>,
+    range: Range<usize>,
+    reversed: bool,
+}
+
+impl Segment {
+    fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    fn get(&self, index: usize, reversed: bool) -> &Crate {
+        if self.reversed != reversed {
+            &self.crates[self.range.end - 1 - index]
+        } else {
+            &self.crates[self.range.start + index]
+        }
+    }
+
+    fn iter(&self, reversed: bool) -> impl Iterator<Item = &Crate> + '_ {
+        let crates = self.crates[self.range.clone()].iter();
+        if self.reversed != reversed {
+            Either::Left(crates.rev())
+        } else {
+            Either::Right(crates)
+        }
+    }
+
+    // Splits off everything after the first `count` crates
+    fn split_off(&mut self, count: usize) -> Self {
+        let range = if self.reversed {
+            let range = self.range.start..self.range.end - count;
+            self.range.start = range.end;
+            range
+        } else {
+            let range = self.range.start + count..self.range.end;
+            self.range.end = range.start;
+            range
+        };
+
+        Self {
+            crates: self.crates.clone(),
+            range,
+            reversed: self.reversed,
+        }
+    }
+}
+
+thread_local! {
+    static SEED: Cell<u64> = Cell::new(0);
+}
+
+// A pseudo-random priority, for keeping the tree balanced
+fn priority() -> u64 {
+    SEED.with(|seed| {
+        let mut z = seed.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+        seed.set(z);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    })
+}
+
+type Tree = Option<Box<Node>>;
+
+// A node of a treap, ordered by position in the stack and heap-ordered by priority
+#[derive(Debug, Clone)]
+struct Node {
+    segment: Segment,
+    priority: u64,
+    // The number of crates in the whole subtree
+    len: usize,
+    // Whether the whole subtree is reversed, and still has to be pushed down to the children
+    reversed: bool,
+    left: Tree,
+    right: Tree,
+}
+
+fn len(tree: &Tree) -> usize {
+    tree.as_ref().map_or(0, |node| node.len)
+}
+
+impl Node {
+    fn new(segment: Segment) -> Box<Self> {
+        Box::new(Self {
+            len: segment.len(),
+            segment,
+            priority: priority(),
+            reversed: false,
+            left: None,
+            right: None,
+        })
+    }
+
+    fn update(&mut self) {
+        self.len = len(&self.left) + self.segment.len() + len(&self.right);
+    }
+
+    fn reverse(&mut self) {
+        self.reversed = !self.reversed;
+    }
+
+    fn push_down(&mut self) {
+        if self.reversed {
+            self.reversed = false;
+            self.segment.reversed = !self.segment.reversed;
+            (self.left, self.right) = (self.right.take(), self.left.take());
+            for child in [&mut self.left, &mut self.right].into_iter().flatten() {
+                child.reverse();
+            }
+        }
+    }
+
+    // The children in order, accounting for a reversal further up
+    fn children(&self, reversed: bool) -> (&Tree, &Tree) {
+        if reversed {
+            (&self.right, &self.left)
+        } else {
+            (&self.left, &self.right)
+        }
+    }
+}
+
+fn merge(left: Tree, right: Tree) -> Tree {
+    match (left, right) {
+        (None, tree) | (tree, None) => tree,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.push_down();
+                left.right = merge(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.push_down();
+                right.left = merge(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+    }
+}
+
+// Splits off everything after the first `count` crates
+fn split(tree: Tree, count: usize) -> (Tree, Tree) {
+    let Some(mut node) = tree else {
+        return (None, None);
+    };
+
+    node.push_down();
+
+    let left_len = len(&node.left);
+    let segment_len = node.segment.len();
+
+    if count <= left_len {
+        let (left, rest) = split(node.left.take(), count);
+        node.left = rest;
+        node.update();
+        (left, Some(node))
+    } else if count >= left_len + segment_len {
+        let (rest, right) = split(node.right.take(), count - left_len - segment_len);
+        node.right = rest;
+        node.update();
+        (Some(node), right)
+    } else {
+        let tail = Node::new(node.segment.split_off(count - left_len));
+        let right = merge(Some(tail), node.right.take());
+        node.update();
+        (Some(node), right)
+    }
+}
+
+/// A stack of crates, ordered from bottom to top, stored as a balanced rope of shared segments.
+///
+/// Moving and reversing crates only touches a logarithmic number of segments, never the crates
+/// themselves.
+#[derive(Debug, Clone, Default)]
+pub struct Stack {
+    root: Tree,
+}
+
+impl Stack {
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn last(&self) -> Option<&Crate> {
+        self.len().checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    pub fn get(&self, mut index: usize) -> Option<&Crate> {
+        let mut tree = &self.root;
+        let mut reversed = false;
+
+        while let Some(node) = tree {
+            reversed ^= node.reversed;
+            let (left, right) = node.children(reversed);
+
+            if index < len(left) {
+                tree = left;
+                continue;
+            }
+
+            index -= len(left);
+
+            if index < node.segment.len() {
+                return Some(node.segment.get(index, reversed));
+            }
+
+            index -= node.segment.len();
+            tree = right;
+        }
+
+        None
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Crate> + '_ {
+        // Walks the tree in order, with the nodes still to visit and whether they're reversed
+        let mut pending = Vec::new();
+        let mut tree = (&self.root, false);
+
+        std::iter::from_fn(move || {
+            while let (Some(node), reversed) = tree {
+                let reversed = reversed ^ node.reversed;
+                pending.push((node, reversed));
+                tree = (node.children(reversed).0, reversed);
+            }
+
+            let (node, reversed) = pending.pop()?;
+            tree = (node.children(reversed).1, reversed);
+            Some(node.segment.iter(reversed))
+        })
+        .flatten()
+    }
+
+    /// Removes the top `count` crates, returning them as a new stack.
+    pub fn split_off(&mut self, count: usize) -> Self {
+        assert!(count <= self.len(), "not enough crates");
+
+        let len = self.len();
+        let (rest, top) = split(self.root.take(), len - count);
+        self.root = rest;
+
+        Self { root: top }
+    }
+
+    pub fn append(&mut self, other: Self) {
+        self.root = merge(self.root.take(), other.root);
+    }
+
+    pub fn reverse(&mut self) {
+        if let Some(root) = &mut self.root {
+            root.reverse();
+        }
+    }
+}
+
+impl From<Vec<Crate>> for Stack {
+    fn from(crates: Vec<Crate>) -> Self {
+        let len = crates.len();
+
+        let root = (len > 0).then(|| {
+            Node::new(Segment {
+                crates: crates.into(),
+                range: 0..len,
+                reversed: false,
+            })
+        });
+
+        Self { root }
+    }
+}
+
+impl PartialEq for Stack {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Stack {}
@@ -1,23 +1,208 @@
-use itertools::Itertools;
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Read, Write},
+};
 
-fn solve(input: &str, len: usize) -> usize {
-    let mut it = input.char_indices();
+pub trait Counter<T> {
+    /// Adds an item, returning its new count.
+    fn add(&mut self, item: T) -> u32;
 
-    while let Some((i, _)) = it.next() {
-        if it.clone().take(len).map(|(_, c)| c).all_unique() {
-            return i + len + 1;
+    /// Removes an item, returning its new count.
+    fn remove(&mut self, item: T) -> u32;
+}
+
+#[derive(Debug, Clone)]
+pub struct ByteCounts([u32; 256]);
+
+impl Default for ByteCounts {
+    fn default() -> Self {
+        Self([0; 256])
+    }
+}
+
+impl Counter<u8> for ByteCounts {
+    fn add(&mut self, item: u8) -> u32 {
+        let count = &mut self.0[item as usize];
+        *count += 1;
+        *count
+    }
+
+    fn remove(&mut self, item: u8) -> u32 {
+        let count = &mut self.0[item as usize];
+        *count -= 1;
+        *count
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CharCounts(HashMap<char, u32>);
+
+impl Counter<char> for CharCounts {
+    fn add(&mut self, item: char) -> u32 {
+        let count = self.0.entry(item).or_default();
+        *count += 1;
+        *count
+    }
+
+    fn remove(&mut self, item: char) -> u32 {
+        let count = self.0.get_mut(&item).unwrap();
+        *count -= 1;
+        let count = *count;
+        if count == 0 {
+            self.0.remove(&item);
+        }
+        count
+    }
+}
+
+/// Finds markers, i.e. windows of `len` distinct items, in constant time per item.
+#[derive(Debug, Clone)]
+pub struct Detector<T, C> {
+    len: usize,
+    window: VecDeque<T>,
+    counts: C,
+    duplicates: usize,
+    position: usize,
+}
+
+pub type ByteDetector = Detector<u8, ByteCounts>;
+pub type CharDetector = Detector<char, CharCounts>;
+
+impl<T: Copy, C: Counter<T> + Default> Detector<T, C> {
+    pub fn new(len: usize) -> Self {
+        assert!(len > 0, "marker length must be positive");
+
+        Self {
+            len,
+            window: VecDeque::with_capacity(len),
+            counts: C::default(),
+            duplicates: 0,
+            position: 0,
         }
     }
 
-    unreachable!()
+    /// Number of items pushed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Pushes the next item, returning the current position if it ends a marker.
+    pub fn push(&mut self, item: T) -> Option<usize> {
+        self.position += 1;
+
+        if self.window.len() == self.len {
+            let oldest = self.window.pop_front().unwrap();
+            if self.counts.remove(oldest) == 1 {
+                self.duplicates -= 1;
+            }
+        }
+
+        self.window.push_back(item);
+        if self.counts.add(item) == 2 {
+            self.duplicates += 1;
+        }
+
+        (self.window.len() == self.len && self.duplicates == 0).then_some(self.position)
+    }
+}
+
+pub fn byte_markers(signal: &[u8], len: usize) -> impl Iterator<Item = usize> + '_ {
+    let mut detector = ByteDetector::new(len);
+    signal.iter().filter_map(move |&item| detector.push(item))
+}
+
+pub fn char_markers(signal: &str, len: usize) -> impl Iterator<Item = usize> + '_ {
+    let mut detector = CharDetector::new(len);
+    signal.chars().filter_map(move |item| detector.push(item))
+}
+
+/// Reports the offset of each marker to a callback as soon as the bytes ending it are written.
+pub struct MarkerWriter<F> {
+    detector: ByteDetector,
+    on_marker: F,
+}
+
+impl<F: FnMut(usize)> MarkerWriter<F> {
+    pub fn new(len: usize, on_marker: F) -> Self {
+        Self {
+            detector: ByteDetector::new(len),
+            on_marker,
+        }
+    }
+}
+
+impl<F: FnMut(usize)> Write for MarkerWriter<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &item in buf {
+            if let Some(position) = self.detector.push(item) {
+                (self.on_marker)(position);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Yields the offset of each marker in a stream, reading only as much as needed to find it.
+pub struct ReadMarkers<R> {
+    reader: R,
+    detector: ByteDetector,
+    buf: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+}
+
+impl<R: Read> ReadMarkers<R> {
+    pub fn new(reader: R, len: usize) -> Self {
+        Self {
+            reader,
+            detector: ByteDetector::new(len),
+            buf: vec![0; 8 * 1024].into(),
+            pos: 0,
+            filled: 0,
+        }
+    }
+}
+
+impl<R: Read> Iterator for ReadMarkers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.pos < self.filled {
+                let item = self.buf[self.pos];
+                self.pos += 1;
+                if let Some(position) = self.detector.push(item) {
+                    return Some(Ok(position));
+                }
+            }
+
+            match self.reader.read(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(filled) => {
+                    self.pos = 0;
+                    self.filled = filled;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+pub fn solve(input: &str, len: usize) -> Option<usize> {
+    byte_markers(input.trim().as_bytes(), len).next()
 }
 
 pub fn part1(input: &str) -> usize {
-    solve(input, 4)
+    solve(input, 4).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    solve(input, 14)
+    solve(input, 14).unwrap()
 }
 
 #[cfg(test)]
@@ -46,4 +231,46 @@ mod tests {
 
         assert_eq!(part2(INPUT), 3986);
     }
+
+    #[test]
+    fn markers_works() {
+        let markers = byte_markers(b"abcdabbcda", 4).collect::<Vec<_>>();
+        assert_eq!(markers, [4, 5, 6, 10]);
+
+        let markers = char_markers("ääöäöüß", 3).collect::<Vec<_>>();
+        assert_eq!(markers, [6, 7]);
+
+        assert_eq!(solve("aabbaabb", 3), None);
+        assert_eq!(solve("ab", 3), None);
+    }
+
+    // Stands in for a pipe or socket, handing out a few bytes at a time
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.0.len()).min(3);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn streaming_works() {
+        let expected = byte_markers(INPUT.as_bytes(), 14).collect::<Vec<_>>();
+
+        let markers = ReadMarkers::new(Trickle(INPUT.as_bytes()), 14);
+        assert_eq!(markers.map(Result::unwrap).collect::<Vec<_>>(), expected);
+
+        let mut markers = Vec::new();
+        let mut writer = MarkerWriter::new(14, |position| markers.push(position));
+        io::copy(&mut Trickle(INPUT.as_bytes()), &mut writer).unwrap();
+        assert_eq!(markers, expected);
+
+        let mut stream = Trickle(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb");
+        let mut markers = ReadMarkers::new(&mut stream, 4);
+        assert_eq!(markers.next().unwrap().unwrap(), 7);
+        assert_eq!(stream.0.len(), 21);
+    }
 }
@@ -1,9 +1,13 @@
-use std::{collections::HashMap as Map, iter};
+use std::{
+    collections::{BTreeMap as Map, HashSet},
+    fmt::Write,
+    iter, mem,
+};
 
 #[derive(Debug)]
 enum Input<'a> {
     List,
-    ChangeDirectory { name: &'a str },
+    ChangeDirectory { target: &'a str },
 }
 
 #[derive(Debug)]
@@ -12,41 +16,94 @@ enum Output<'a> {
     Directory { name: &'a str },
 }
 
+impl<'a> Output<'a> {
+    fn name(&self) -> &'a str {
+        match self {
+            Self::File { name, .. } | Self::Directory { name } => name,
+        }
+    }
+}
+
 #[derive(Debug)]
 enum IO<'a> {
     Input(Input<'a>),
     Output(Output<'a>),
 }
 
-impl<'a> From<&'a str> for IO<'a> {
-    fn from(s: &'a str) -> Self {
+impl<'a> IO<'a> {
+    /// Parses a line of the transcript, returning `None` for blank lines and `#` comments.
+    fn parse(s: &'a str) -> Result<Option<Self>, DiagnosticKind> {
+        let s = s.trim();
+
+        if s.is_empty() || s.starts_with('#') {
+            return Ok(None);
+        }
+
         let mut terms = s.split_whitespace();
+        let mut next = || terms.next().ok_or(DiagnosticKind::MalformedLine);
 
-        match terms.next().unwrap() {
-            "$" => match terms.next().unwrap() {
+        Ok(Some(match next()? {
+            "$" => match next()? {
                 "ls" => IO::Input(Input::List),
-                "cd" => IO::Input(Input::ChangeDirectory {
-                    name: terms.next().unwrap(),
-                }),
-                _ => panic!(),
+                "cd" => IO::Input(Input::ChangeDirectory { target: next()? }),
+                command => return Err(DiagnosticKind::UnknownCommand(command.to_owned())),
             },
-            "dir" => IO::Output(Output::Directory {
-                name: terms.next().unwrap(),
-            }),
+            "dir" => IO::Output(Output::Directory { name: next()? }),
             rem => IO::Output(Output::File {
-                size: rem.parse().unwrap(),
-                name: terms.next().unwrap(),
+                size: rem.parse().map_err(|_| DiagnosticKind::MalformedLine)?,
+                name: next()?,
             }),
-        }
+        }))
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    MalformedLine,
+    UnknownCommand(String),
+    /// Output that doesn't follow an `ls`.
+    UnexpectedOutput,
+    /// A `cd` into a directory missing from an earlier listing of its parent.
+    UnknownDirectory(String),
+    NotADirectory(String),
+    /// A file listed again with a different size.
+    ConflictingSize {
+        path: String,
+        previous: u32,
+        current: u32,
+    },
+    /// An entry listed again as a file instead of a directory, or the other way around.
+    ConflictingKind(String),
+    /// An entry missing from a repeated listing of its directory.
+    MissingEntry(String),
+}
+
 #[derive(Debug)]
-enum Filesystem<'a> {
+pub enum Filesystem<'a> {
     File { size: u32 },
     Directory(Map<&'a str, Filesystem<'a>>),
 }
 
+fn join(path: &str, name: &str) -> String {
+    if path.ends_with('/') {
+        format!("{path}{name}")
+    } else {
+        format!("{path}/{name}")
+    }
+}
+
+impl Default for Filesystem<'_> {
+    fn default() -> Self {
+        Self::dir()
+    }
+}
+
 impl<'a> Filesystem<'a> {
     fn dir() -> Self {
         Self::Directory(Map::new())
@@ -56,123 +113,557 @@ impl<'a> Filesystem<'a> {
         Self::File { size }
     }
 
-    fn insert(&mut self, path: &[&'a str], output: Output<'a>) {
-        if let Filesystem::Directory(content) = self {
-            if let Some((name, path)) = path.split_first() {
-                let fs = content.entry(name).or_insert_with(Filesystem::dir);
-                fs.insert(path, output)
-            } else {
-                match output {
-                    Output::File { name, size } => {
-                        content.insert(name, Filesystem::file(size));
-                    }
-                    Output::Directory { name } => {
-                        content.insert(name, Filesystem::dir());
-                    }
-                }
-            }
+    fn content_mut(&mut self, path: &[&'a str]) -> &mut Map<&'a str, Filesystem<'a>> {
+        let node = path.iter().fold(self, |node, name| match node {
+            Self::Directory(content) => content.get_mut(name).unwrap(),
+            Self::File { .. } => unreachable!(),
+        });
+
+        match node {
+            Self::Directory(content) => content,
+            Self::File { .. } => unreachable!(),
         }
     }
 
-    fn size(&self) -> u32 {
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Self::Directory(_))
+    }
+
+    pub fn size(&self) -> u32 {
         match self {
             Self::File { size } => *size,
             Self::Directory(content) => content.values().map(Self::size).sum(),
         }
     }
 
-    fn nodes(&'a self) -> impl Iterator<Item = &'a Filesystem<'a>> {
-        let mut nodes = vec![self];
+    /// The sizes of all directories, computed in a single pass.
+    pub fn directory_sizes(&'a self) -> DirectorySizes<'a> {
+        let mut directories = Vec::new();
+        let mut nodes = vec![("/", None, self)];
+
+        while let Some((name, parent, node)) = nodes.pop() {
+            if let Self::Directory(content) = node {
+                let index = directories.len();
+                let size = content
+                    .values()
+                    .filter_map(|node| match node {
+                        Self::File { size } => Some(*size),
+                        Self::Directory(_) => None,
+                    })
+                    .sum();
+
+                directories.push(DirectorySize { name, parent, size });
+                nodes.extend(
+                    content
+                        .iter()
+                        .map(|(name, node)| (*name, Some(index), node)),
+                );
+            }
+        }
+
+        // Children always come after their parent, so sizes can be accumulated backwards
+        for index in (0..directories.len()).rev() {
+            if let Some(parent) = directories[index].parent {
+                directories[parent].size += directories[index].size;
+            }
+        }
+
+        DirectorySizes(directories)
+    }
+
+    /// Looks up a node by its absolute path, such as `/a/e/i`.
+    pub fn get(&self, path: &str) -> Option<&Self> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self, |node, name| match node {
+                Self::Directory(content) => content.get(name),
+                Self::File { .. } => None,
+            })
+    }
+
+    /// The entries of a directory, sorted by name.
+    pub fn list(&'a self) -> Option<impl Iterator<Item = (&'a str, &'a Filesystem<'a>)>> {
+        match self {
+            Self::Directory(content) => Some(content.iter().map(|(&name, node)| (name, node))),
+            Self::File { .. } => None,
+        }
+    }
+
+    /// All nodes with their absolute paths, parents before children.
+    pub fn walk(&'a self) -> impl Iterator<Item = (String, &'a Filesystem<'a>)> {
+        let mut nodes = vec![("/".to_owned(), self)];
 
         iter::from_fn(move || {
-            let node = nodes.pop();
-            if let Some(Self::Directory(content)) = node {
-                nodes.extend(content.values());
+            let (path, node) = nodes.pop()?;
+            if let Self::Directory(content) = node {
+                nodes.extend(
+                    content
+                        .iter()
+                        .rev()
+                        .map(|(name, node)| (join(&path, name), node)),
+                );
             }
-            node
+            Some((path, node))
         })
     }
-}
 
-enum IOState {
-    Reading,
-    Writing,
-}
+    /// The total size of each directory, children before parents, like `du`.
+    pub fn du(&self) -> String {
+        fn visit(node: &Filesystem, path: &str, output: &mut String) -> u32 {
+            match node {
+                Filesystem::File { size } => *size,
+                Filesystem::Directory(content) => {
+                    let size = content
+                        .iter()
+                        .map(|(name, node)| visit(node, &join(path, name), output))
+                        .sum();
+                    writeln!(output, "{size}\t{path}").unwrap();
+                    size
+                }
+            }
+        }
 
-fn parse_input(input: &str) -> Filesystem {
-    let mut node = Filesystem::dir();
-    let mut path = Vec::new();
-    let mut state = IOState::Reading;
+        let mut output = String::new();
+        visit(self, "/", &mut output);
+        output
+    }
 
-    for line in input.trim().lines() {
-        match (&state, line.into()) {
-            (_, IO::Input(command)) => match command {
-                Input::ChangeDirectory { name } => {
-                    match name {
-                        "/" => {
-                            path.clear();
-                        }
-                        ".." => {
-                            path.pop();
-                        }
-                        name => {
-                            path.push(name);
-                        }
-                    };
-                    state = IOState::Reading;
+    /// The directory tree, in the format of the puzzle description.
+    pub fn tree(&self) -> String {
+        fn visit(node: &Filesystem, name: &str, depth: usize, output: &mut String) {
+            let indent = depth * 2;
+            match node {
+                Filesystem::File { size } => {
+                    writeln!(output, "{:indent$}- {name} (file, size={size})", "").unwrap();
                 }
-                Input::List => {
-                    state = IOState::Writing;
+                Filesystem::Directory(content) => {
+                    writeln!(output, "{:indent$}- {name} (dir)", "").unwrap();
+                    for (name, node) in content {
+                        visit(node, name, depth + 1, output);
+                    }
                 }
-            },
-            (IOState::Writing, IO::Output(output)) => {
-                node.insert(&path, output);
             }
-            (IOState::Reading, IO::Output(_)) => {
-                // Ignore output
+        }
+
+        let mut output = String::new();
+        visit(self, "/", 0, &mut output);
+        output
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DirectorySize<'a> {
+    pub name: &'a str,
+    pub parent: Option<usize>,
+    pub size: u32,
+}
+
+/// The total size of each directory, parents before children, starting with the root.
+#[derive(Debug)]
+pub struct DirectorySizes<'a>(Vec<DirectorySize<'a>>);
+
+impl<'a> DirectorySizes<'a> {
+    pub fn iter(&self) -> impl Iterator<Item = &DirectorySize<'a>> + '_ {
+        self.0.iter()
+    }
+
+    pub fn total_size(&self) -> u32 {
+        self.0[0].size
+    }
+
+    /// The absolute path of the directory at `index`.
+    pub fn path(&self, index: usize) -> String {
+        let mut names = Vec::new();
+        let mut current = Some(index);
+
+        while let Some(index) = current {
+            let directory = &self.0[index];
+            if directory.parent.is_some() {
+                names.push(directory.name);
             }
+            current = directory.parent;
         }
+
+        names.reverse();
+        format!("/{}", names.join("/"))
     }
+}
 
-    node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deletion {
+    pub paths: Vec<String>,
+    pub size: u32,
 }
 
-pub fn part1(input: &str) -> u32 {
-    let fs = parse_input(input);
+/// Plans which directories to delete to make room for an update.
+#[derive(Debug, Clone, Copy)]
+pub struct Planner {
+    pub capacity: u32,
+    pub required: u32,
+    /// The most memory, in bytes, that searching for a set of directories may use.
+    ///
+    /// Defaults to 256 MiB, which comfortably covers the puzzle input.
+    pub memory_limit: usize,
+}
+
+/// Searching for a set of directories would need more memory than the planner allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchTooLarge {
+    pub bytes: usize,
+}
+
+// ORs `source`, shifted up by `shift` bits, into `target`, dropping bits past its end
+fn shift_or(target: &mut [u64], source: &[u64], shift: usize) {
+    let (words, bits) = (shift / 64, shift % 64);
+    let word = |index: usize| source.get(index).copied().unwrap_or(0);
+
+    for index in (words..target.len()).rev() {
+        let lo = word(index - words) << bits;
+        let hi = match index.checked_sub(words + 1) {
+            Some(i) if bits > 0 => word(i) >> (64 - bits),
+            _ => 0,
+        };
+        target[index] |= lo | hi;
+    }
+}
+
+fn contains(set: &[u64], value: usize) -> bool {
+    set.get(value / 64)
+        .map_or(false, |word| word & (1 << (value % 64)) != 0)
+}
+
+impl Planner {
+    pub fn new(capacity: u32, required: u32) -> Self {
+        Self {
+            capacity,
+            required,
+            memory_limit: 256 << 20,
+        }
+    }
+
+    /// How much space must be freed up, given the total size of the filesystem.
+    pub fn needed(&self, total_size: u32) -> u32 {
+        let free = self.capacity.saturating_sub(total_size);
+        self.required.saturating_sub(free)
+    }
+
+    /// The smallest directory that frees up enough space on its own.
+    pub fn best_directory(&self, sizes: &DirectorySizes) -> Option<Deletion> {
+        let needed = self.needed(sizes.total_size());
+
+        let (index, directory) = sizes
+            .iter()
+            .enumerate()
+            .filter(|(_, directory)| directory.size >= needed)
+            .min_by_key(|(_, directory)| directory.size)?;
+
+        Some(Deletion {
+            paths: vec![sizes.path(index)],
+            size: directory.size,
+        })
+    }
+
+    /// The set of non-nested directories with the smallest total size that frees up enough space.
+    ///
+    /// Runs in time proportional to the number of directories times the size of the best single
+    /// directory, and keeps a set of that many bits for each directory without subdirectories,
+    /// failing up front if that would go over the memory limit.
+    pub fn best_directories(
+        &self,
+        sizes: &DirectorySizes,
+    ) -> Result<Option<Deletion>, SearchTooLarge> {
+        let Some(single) = self.best_directory(sizes) else {
+            return Ok(None);
+        };
+        let needed = self.needed(sizes.total_size()) as usize;
+        let directories = &sizes.0;
+
+        let mut children = vec![Vec::new(); directories.len()];
+        for (index, directory) in directories.iter().enumerate() {
+            if let Some(parent) = directory.parent {
+                children[parent].push(index);
+            }
+        }
+
+        // Directories in preorder, with where each one's subtree ends
+        let mut order = Vec::with_capacity(directories.len());
+        let mut ends = vec![0; directories.len()];
+        let mut stack = vec![(0, false)];
+
+        while let Some((index, visited)) = stack.pop() {
+            if visited {
+                ends[index] = order.len();
+            } else {
+                order.push(index);
+                stack.push((index, true));
+                stack.extend(children[index].iter().rev().map(|&child| (child, false)));
+            }
+        }
+
+        // The size of the files directly in each suffix of the preorder, which no total
+        // achievable from that suffix can go over
+        let mut files = vec![0; order.len() + 1];
+        for (position, &index) in order.iter().enumerate().rev() {
+            let nested = children[index].iter().map(|&child| directories[child].size);
+            let own = directories[index].size - nested.sum::<u32>();
+            files[position] = files[position + 1] + own as usize;
+        }
+
+        // Only totals smaller than the best single directory are worth tracking
+        let width = single.size as usize;
+        let words = |position: usize| (width.min(files[position] + 1) + 63) / 64;
+
+        // Reconstruction only reads the totals from the start, and from where each subtree ends
+        let mut keep = vec![false; order.len() + 1];
+        keep[0] = true;
+        for &end in &ends {
+            keep[end] = true;
+        }
+
+        let kept = (0..=order.len()).filter(|&position| keep[position]);
+        let bytes = (kept.map(words).sum::<usize>() + words(0)) * mem::size_of::<u64>();
+        if bytes > self.memory_limit {
+            return Err(SearchTooLarge { bytes });
+        }
+
+        // The totals achievable by deleting directories from each kept suffix of the preorder,
+        // built up from the totals of the suffix after it
+        let mut totals = vec![Vec::new(); order.len() + 1];
+        let mut total = vec![0u64; words(order.len())];
+        if let Some(word) = total.first_mut() {
+            *word = 1;
+        }
+
+        for position in (0..=order.len()).rev() {
+            if position < order.len() {
+                let index = order[position];
+                total.resize(words(position), 0);
+                let size = directories[index].size as usize;
+                if size < width {
+                    shift_or(&mut total, &totals[ends[index]], size);
+                }
+            }
+            if keep[position] {
+                totals[position] = total.clone();
+            }
+        }
+
+        let Some(size) = (needed..width).find(|&size| contains(&totals[0], size)) else {
+            return Ok(Some(single));
+        };
 
-    let mut total_size = 0;
+        let mut paths = Vec::new();
+        let (mut position, mut remaining) = (0, size);
 
-    for node in fs.nodes() {
-        if let Filesystem::Directory(_) = node {
-            let size = node.size();
-            if size <= 100_000 {
-                total_size += size;
+        while remaining > 0 {
+            let index = order[position];
+            let size = directories[index].size as usize;
+            if size <= remaining && contains(&totals[ends[index]], remaining - size) {
+                paths.push(sizes.path(index));
+                remaining -= size;
+                position = ends[index];
+            } else {
+                position += 1;
             }
         }
+
+        paths.sort_unstable();
+
+        Ok(Some(Deletion {
+            paths,
+            size: size as u32,
+        }))
     }
+}
 
-    total_size
+fn format_path(path: &[&str], name: &str) -> String {
+    path.iter()
+        .chain([&name])
+        .fold(String::new(), |path, name| path + "/" + name)
 }
 
-pub fn part2(input: &str) -> u32 {
-    let fs = parse_input(input);
+struct Listing<'a> {
+    line: usize,
+    names: HashSet<&'a str>,
+}
 
-    let total_size = fs.size();
-    let free_size = 70_000_000 - total_size;
-    let needed_size = 30_000_000 - free_size;
+#[derive(Default)]
+struct Parser<'a> {
+    fs: Filesystem<'a>,
+    diagnostics: Vec<Diagnostic>,
+    path: Vec<&'a str>,
+    listed: HashSet<Vec<&'a str>>,
+    listing: Option<Listing<'a>>,
+}
 
-    let mut best_size: Option<u32> = None;
+impl<'a> Parser<'a> {
+    fn report(&mut self, line: usize, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic { line, kind });
+    }
+
+    fn finish_listing(&mut self) {
+        if let Some(listing) = self.listing.take() {
+            if !self.listed.insert(self.path.clone()) {
+                let content = self.fs.content_mut(&self.path);
+                let missing = content
+                    .keys()
+                    .filter(|name| !listing.names.contains(*name))
+                    .map(|name| DiagnosticKind::MissingEntry(format_path(&self.path, name)))
+                    .collect::<Vec<_>>();
+
+                for kind in missing {
+                    self.report(listing.line, kind);
+                }
+            }
+        }
+    }
+
+    // Stays in the current directory if the target isn't a directory
+    fn change_directory(&mut self, line: usize, target: &'a str) {
+        let previous = self.path.clone();
+
+        if target.starts_with('/') {
+            self.path.clear();
+        }
 
-    for node in fs.nodes() {
-        if let Filesystem::Directory(_) = node {
-            let size = node.size();
-            if size >= needed_size && size <= best_size.unwrap_or(size) {
-                best_size = Some(size)
+        for name in target.split('/').filter(|name| !name.is_empty()) {
+            match name {
+                "." => {}
+                ".." => {
+                    self.path.pop();
+                }
+                name => {
+                    let content = self.fs.content_mut(&self.path);
+                    match content.get(name) {
+                        Some(Filesystem::Directory(_)) => {}
+                        Some(Filesystem::File { .. }) => {
+                            let path = format_path(&self.path, name);
+                            self.report(line, DiagnosticKind::NotADirectory(path));
+                            self.path = previous;
+                            return;
+                        }
+                        None => {
+                            content.insert(name, Filesystem::dir());
+                            if self.listed.contains(&self.path) {
+                                let path = format_path(&self.path, name);
+                                self.report(line, DiagnosticKind::UnknownDirectory(path));
+                            }
+                        }
+                    }
+                    self.path.push(name);
+                }
             }
         }
     }
 
-    best_size.unwrap()
+    fn list_entry(&mut self, line: usize, output: Output<'a>) {
+        let name = output.name();
+
+        let Some(listing) = &mut self.listing else {
+            return self.report(line, DiagnosticKind::UnexpectedOutput);
+        };
+
+        listing.names.insert(name);
+
+        let path = format_path(&self.path, name);
+        let content = self.fs.content_mut(&self.path);
+
+        let kind = match (content.get(name), output) {
+            (None, Output::File { size, .. }) => {
+                content.insert(name, Filesystem::file(size));
+                None
+            }
+            (None, Output::Directory { .. }) => {
+                content.insert(name, Filesystem::dir());
+                None
+            }
+            (Some(&Filesystem::File { size: previous }), Output::File { size, .. }) => {
+                content.insert(name, Filesystem::file(size));
+                (previous != size).then_some(DiagnosticKind::ConflictingSize {
+                    path,
+                    previous,
+                    current: size,
+                })
+            }
+            (Some(Filesystem::Directory(_)), Output::Directory { .. }) => None,
+            (Some(Filesystem::Directory(_)), Output::File { size, .. }) => {
+                content.insert(name, Filesystem::file(size));
+                Some(DiagnosticKind::ConflictingKind(path))
+            }
+            (Some(Filesystem::File { .. }), Output::Directory { .. }) => {
+                content.insert(name, Filesystem::dir());
+                Some(DiagnosticKind::ConflictingKind(path))
+            }
+        };
+
+        if let Some(kind) = kind {
+            self.report(line, kind);
+        }
+    }
+}
+
+/// Builds the filesystem from a transcript, reporting any inconsistencies found along the way.
+pub fn parse_transcript(input: &str) -> (Filesystem, Vec<Diagnostic>) {
+    let mut parser = Parser::default();
+
+    for (index, s) in input.lines().enumerate() {
+        let line = index + 1;
+
+        match IO::parse(s) {
+            Ok(None) => {}
+            Ok(Some(IO::Input(command))) => {
+                parser.finish_listing();
+                match command {
+                    Input::ChangeDirectory { target } => {
+                        parser.change_directory(line, target);
+                    }
+                    Input::List => {
+                        parser.listing = Some(Listing {
+                            line,
+                            names: HashSet::new(),
+                        });
+                    }
+                }
+            }
+            Ok(Some(IO::Output(output))) => {
+                parser.list_entry(line, output);
+            }
+            Err(kind) => {
+                if s.trim_start().starts_with('$') {
+                    parser.finish_listing();
+                }
+                parser.report(line, kind);
+            }
+        }
+    }
+
+    parser.finish_listing();
+
+    (parser.fs, parser.diagnostics)
+}
+
+pub fn parse_input(input: &str) -> Filesystem {
+    parse_transcript(input).0
+}
+
+pub fn part1(input: &str) -> u32 {
+    let fs = parse_input(input);
+
+    fs.directory_sizes()
+        .iter()
+        .map(|directory| directory.size)
+        .filter(|&size| size <= 100_000)
+        .sum()
+}
+
+pub fn part2(input: &str) -> u32 {
+    let fs = parse_input(input);
+
+    let sizes = fs.directory_sizes();
+
+    let planner = Planner::new(70_000_000, 30_000_000);
+
+    planner.best_directory(&sizes).unwrap().size
 }
 
 #[cfg(test)]
@@ -193,4 +684,214 @@ mod tests {
         assert_eq!(part2(INPUT_TEST), 24_933_642);
         assert_eq!(part2(INPUT), 5_756_764);
     }
+
+    #[test]
+    fn planner_works() {
+        let fs = parse_input(INPUT_TEST);
+        let sizes = fs.directory_sizes();
+
+        let planner = Planner::new(70_000_000, 30_000_000);
+        let deletion = planner.best_directory(&sizes).unwrap();
+        assert_eq!(deletion.paths, ["/d"]);
+        assert_eq!(planner.best_directories(&sizes), Ok(Some(deletion)));
+
+        let planner = Planner::new(48_500_000, 200_000);
+        assert_eq!(planner.needed(sizes.total_size()), 81_165);
+        let deletion = planner.best_directory(&sizes).unwrap();
+        assert_eq!(
+            (deletion.paths, deletion.size),
+            (vec!["/a".to_owned()], 94_853)
+        );
+
+        let planner = Planner::new(48_381_165, 500);
+        let deletion = planner.best_directories(&sizes).unwrap().unwrap();
+        assert_eq!(
+            (deletion.paths, deletion.size),
+            (vec!["/a/e".to_owned()], 584)
+        );
+
+        let input = concat!(
+            "$ cd /\n$ ls\ndir x\ndir z\n",
+            "$ cd x\n$ ls\n2 a\ndir w\n$ cd w\n$ ls\n58 b\n",
+            "$ cd /\n$ cd z\n$ ls\n150 c\ndir y\n$ cd y\n$ ls\n45 d\n",
+        );
+        let fs = parse_input(input);
+        let sizes = fs.directory_sizes();
+
+        let planner = Planner::new(255, 100);
+        assert_eq!(planner.best_directory(&sizes).unwrap().paths, ["/z"]);
+        let deletion = planner.best_directories(&sizes).unwrap().unwrap();
+        assert_eq!(
+            (deletion.paths, deletion.size),
+            (vec!["/x/w".into(), "/z/y".into()], 103)
+        );
+
+        let planner = Planner::new(255, 110);
+        assert_eq!(
+            planner.best_directories(&sizes),
+            Ok(planner.best_directory(&sizes))
+        );
+
+        let planner = Planner::new(300, 10);
+        let deletion = planner.best_directories(&sizes).unwrap().unwrap();
+        assert_eq!((deletion.paths.len(), deletion.size), (0, 0));
+
+        let fs = parse_input(INPUT);
+        let sizes = fs.directory_sizes();
+
+        for required in [30_000_000, 27_000_000] {
+            let planner = Planner::new(70_000_000, required);
+            let single = planner.best_directory(&sizes).unwrap();
+            let set = planner.best_directories(&sizes).unwrap().unwrap();
+
+            let needed = planner.needed(sizes.total_size());
+            let paths = set.paths.iter().map(|path| fs.get(path).unwrap().size());
+            assert_eq!(paths.sum::<u32>(), set.size);
+            assert!(needed <= set.size && set.size <= single.size);
+        }
+
+        assert_eq!(
+            Planner::new(100, 100_000_000).best_directories(&sizes),
+            Ok(None)
+        );
+
+        let mut planner = Planner::new(70_000_000, 30_000_000);
+        planner.memory_limit = 1 << 20;
+        let error = planner.best_directories(&sizes).unwrap_err();
+        assert!(error.bytes > planner.memory_limit);
+    }
+
+    #[test]
+    fn diagnostics_work() {
+        let transcript = concat!(
+            "# Exploring the disk\n",
+            "$ cd /\n",
+            "$ ls\n",
+            "dir a\n",
+            "100 b.txt\n",
+            "$ cd a\n",
+            "$ ls\n",
+            "50 c\n",
+            "$ cd /a/../a\n",
+            "$ ls\n",
+            "60 c\n",
+            "dir d\n",
+            "$ cd /\n",
+            "$ ls\n",
+            "dir a\n",
+            "$ cd x\n",
+            "$ rm -rf a\n",
+            "10 stray\n",
+            "$ cd /b.txt\n",
+            "$ cd\n",
+        );
+
+        let (fs, diagnostics) = parse_transcript(transcript);
+
+        let expected = [
+            (
+                11,
+                DiagnosticKind::ConflictingSize {
+                    path: "/a/c".to_owned(),
+                    previous: 50,
+                    current: 60,
+                },
+            ),
+            (14, DiagnosticKind::MissingEntry("/b.txt".to_owned())),
+            (16, DiagnosticKind::UnknownDirectory("/x".to_owned())),
+            (17, DiagnosticKind::UnknownCommand("rm".to_owned())),
+            (18, DiagnosticKind::UnexpectedOutput),
+            (19, DiagnosticKind::NotADirectory("/b.txt".to_owned())),
+            (20, DiagnosticKind::MalformedLine),
+        ]
+        .map(|(line, kind)| Diagnostic { line, kind });
+
+        assert_eq!(diagnostics, expected);
+        assert_eq!(fs.size(), 160);
+        assert!(fs.get("/a/d").unwrap().is_dir());
+        assert!(fs.get("/x").unwrap().is_dir());
+
+        let transcript = concat!(
+            "$ cd /\n",
+            "$ ls\n",
+            "dir a\n",
+            "$ cd a\n",
+            "$ ls\n",
+            "100 b.txt\n",
+            "$ cd /\n",
+            "$ cd /a/b.txt/x\n",
+            "$ ls\n",
+            "dir a\n",
+            "5 y\n",
+        );
+
+        let (fs, diagnostics) = parse_transcript(transcript);
+        let kind = DiagnosticKind::NotADirectory("/a/b.txt".to_owned());
+        assert_eq!(diagnostics, [Diagnostic { line: 8, kind }]);
+        assert!(fs.get("/y").is_some() && fs.get("/a/y").is_none());
+
+        assert_eq!(parse_transcript(INPUT).1, []);
+    }
+
+    #[test]
+    fn filesystem_works() {
+        let fs = parse_input(INPUT_TEST);
+
+        assert_eq!(fs.get("/a/e/i").map(Filesystem::size), Some(584));
+        assert_eq!(fs.get("/a/").map(Filesystem::size), Some(94_853));
+        assert!(fs.get("/a/e/i/j").is_none());
+        assert!(fs.get("/b.txt").unwrap().list().is_none());
+
+        let names = fs.get("/a").unwrap().list().unwrap().map(|(name, _)| name);
+        assert_eq!(names.collect::<Vec<_>>(), ["e", "f", "g", "h.lst"]);
+
+        let dirs = fs.walk().filter(|(_, node)| node.is_dir());
+        let paths = dirs.map(|(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(paths, ["/", "/a", "/a/e", "/d"]);
+
+        let sizes = fs.directory_sizes();
+        let sizes = sizes
+            .iter()
+            .enumerate()
+            .map(|(index, directory)| (sizes.path(index), directory.size));
+        assert_eq!(
+            sizes.collect::<Vec<_>>(),
+            [
+                ("/".to_owned(), 48_381_165),
+                ("/d".to_owned(), 24_933_642),
+                ("/a".to_owned(), 94_853),
+                ("/a/e".to_owned(), 584)
+            ]
+        );
+
+        assert_eq!(
+            fs.du(),
+            concat!(
+                "584\t/a/e\n",
+                "94853\t/a\n",
+                "24933642\t/d\n",
+                "48381165\t/\n"
+            )
+        );
+
+        assert_eq!(
+            fs.tree(),
+            concat!(
+                "- / (dir)\n",
+                "  - a (dir)\n",
+                "    - e (dir)\n",
+                "      - i (file, size=584)\n",
+                "    - f (file, size=29116)\n",
+                "    - g (file, size=2557)\n",
+                "    - h.lst (file, size=62596)\n",
+                "  - b.txt (file, size=14848514)\n",
+                "  - c.dat (file, size=8504156)\n",
+                "  - d (dir)\n",
+                "    - d.ext (file, size=5626152)\n",
+                "    - d.log (file, size=8033020)\n",
+                "    - j (file, size=4060174)\n",
+                "    - k (file, size=7214296)\n",
+            )
+        );
+    }
 }
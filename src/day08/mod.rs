@@ -2,19 +2,112 @@ use std::{ops::Index, str::FromStr};
 
 use itertools::Itertools;
 
-#[derive(Debug)]
-struct Grid {
-    data: Box<[u8]>,
+/// A rectangular grid, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T = u8> {
+    data: Box<[T]>,
     rows: usize,
+    cols: usize,
 }
 
-impl Grid {
-    fn rows(&self) -> usize {
+impl<T> Grid<T> {
+    pub fn rows(&self) -> usize {
         self.rows
     }
 
-    fn cols(&self) -> usize {
-        self.data.len() / self.rows
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, (x, y): (usize, usize)) -> Option<&T> {
+        (x < self.cols && y < self.rows).then(|| &self.data[x + y * self.cols])
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.data[y * self.cols..][..self.cols]
+    }
+
+    /// All cells, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.data.iter()
+    }
+
+    // Walks every row and column in both directions, visiting each cell with its position along
+    // the line and the state of that line, which starts out as `init`. Columns are walked side by
+    // side, one row at a time, to keep memory accesses sequential.
+    fn scan<S: Clone>(&self, init: S, mut visit: impl FnMut(&mut S, usize, usize)) {
+        let (rows, cols) = (self.rows, self.cols);
+
+        for y in 0..rows {
+            let mut state = init.clone();
+            for x in 0..cols {
+                visit(&mut state, x, x + y * cols);
+            }
+            let mut state = init.clone();
+            for x in (0..cols).rev() {
+                visit(&mut state, cols - 1 - x, x + y * cols);
+            }
+        }
+
+        let mut states = vec![init.clone(); cols];
+        for y in 0..rows {
+            for (x, state) in states.iter_mut().enumerate() {
+                visit(state, y, x + y * cols);
+            }
+        }
+
+        let mut states = vec![init; cols];
+        for y in (0..rows).rev() {
+            for (x, state) in states.iter_mut().enumerate() {
+                visit(state, rows - 1 - y, x + y * cols);
+            }
+        }
+    }
+
+    // A grid of the same shape, with the given cells
+    fn with_data<U>(&self, data: Vec<U>) -> Grid<U> {
+        debug_assert_eq!(data.len(), self.data.len());
+        Grid {
+            data: data.into_boxed_slice(),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+}
+
+impl Grid {
+    /// Whether each tree is visible from outside the grid, keeping a running maximum along each
+    /// line.
+    pub fn visibility(&self) -> Grid<bool> {
+        let mut visible = vec![false; self.data.len()];
+
+        self.scan(None, |tallest, _, index| {
+            let height = Some(self.data[index]);
+            if *tallest < height {
+                visible[index] = true;
+                *tallest = height;
+            }
+        });
+
+        self.with_data(visible)
+    }
+
+    /// The scenic score of each tree, keeping track along each line of the nearest tree at least
+    /// as tall as each height.
+    pub fn scenic_scores(&self) -> Grid<usize> {
+        let mut scores = vec![1; self.data.len()];
+
+        self.scan([0; 10], |nearest: &mut [usize; 10], position, index| {
+            let height = self.data[index] as usize;
+            scores[index] *= position - nearest[height];
+            for (other, nearest) in nearest.iter_mut().enumerate() {
+                if other <= height {
+                    *nearest = position;
+                }
+            }
+        });
+
+        self.with_data(scores)
     }
 }
 
@@ -23,37 +116,55 @@ impl FromStr for Grid {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut rows = 0;
+        let mut cols = None;
         let mut grid = Vec::new();
 
         for line in s.trim().lines() {
+            let len = grid.len();
+
+            for c in line.chars() {
+                grid.push(c.to_digit(10).ok_or(())? as u8);
+            }
+
+            if *cols.get_or_insert(grid.len() - len) != grid.len() - len {
+                return Err(());
+            }
+
             rows += 1;
-            grid.extend(line.chars().filter_map(|c| c.to_digit(10).map(|d| d as u8)))
         }
 
         Ok(Grid {
             data: grid.into_boxed_slice(),
             rows,
+            cols: cols.unwrap_or(0),
         })
     }
 }
 
-impl Index<(usize, usize)> for Grid {
-    type Output = u8;
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
 
     fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
-        debug_assert!(x <= self.cols());
-        debug_assert!(y <= self.rows());
-        &self.data[x + y * self.rows]
+        debug_assert!(x < self.cols());
+        debug_assert!(y < self.rows());
+        &self.data[x + y * self.cols]
     }
 }
 
-pub fn part1(input: &str) -> usize {
-    let grid: Grid = input.parse().unwrap();
+pub fn count_visible(grid: &Grid) -> usize {
+    grid.visibility().iter().filter(|&&visible| visible).count()
+}
 
+pub fn max_scenic_score(grid: &Grid) -> usize {
+    grid.scenic_scores().iter().copied().max().unwrap()
+}
+
+/// Counts the visible trees by checking every ray from every tree, for reference.
+pub fn count_visible_naive(grid: &Grid) -> usize {
     let rows = grid.rows();
     let cols = grid.cols();
 
-    let nodes = (0..rows).cartesian_product(0..cols);
+    let nodes = (0..cols).cartesian_product(0..rows);
 
     nodes
         .filter(|&node| {
@@ -68,13 +179,16 @@ pub fn part1(input: &str) -> usize {
         .count()
 }
 
-pub fn part2(input: &str) -> usize {
-    let grid: Grid = input.parse().unwrap();
-
+/// Finds the highest scenic score by walking every ray from every tree.
+///
+/// With only ten heights, each walk ends within a few trees of where it starts unless the trees
+/// along the way count up in height, so this is close to linear as well, and on random forests
+/// it's faster than building the whole map with [`Grid::scenic_scores`].
+pub fn max_scenic_score_naive(grid: &Grid) -> usize {
     let rows = grid.rows();
     let cols = grid.cols();
 
-    let nodes = (0..rows).cartesian_product(0..cols);
+    let nodes = (0..cols).cartesian_product(0..rows);
 
     nodes
         .map(|node| {
@@ -104,6 +218,18 @@ pub fn part2(input: &str) -> usize {
         .unwrap()
 }
 
+pub fn part1(input: &str) -> usize {
+    let grid: Grid = input.parse().unwrap();
+
+    count_visible(&grid)
+}
+
+pub fn part2(input: &str) -> usize {
+    let grid: Grid = input.parse().unwrap();
+
+    max_scenic_score_naive(&grid)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,4 +248,74 @@ mod tests {
         assert_eq!(part2(INPUT_TEST), 8);
         assert_eq!(part2(INPUT), 595_080);
     }
+
+    // A forest of `cols` by `rows` trees, with pseudo-random heights
+    fn forest(cols: usize, rows: usize, seed: usize) -> String {
+        let mut state = seed;
+        let mut forest = String::new();
+
+        for _ in 0..rows {
+            for _ in 0..cols {
+                state = (state * 1_103_515_245 + 12_345) % (1 << 31);
+                forest.push(char::from(b'0' + (state >> 16) as u8 % 10));
+            }
+            forest.push('\n');
+        }
+
+        forest
+    }
+
+    #[test]
+    fn matches_naive() {
+        let forests = (1..40).map(|size| forest(size, size, size));
+        let rectangles = (1..20).map(|size| forest(size, 41 - 2 * size, size));
+
+        for input in [INPUT_TEST.to_owned(), INPUT.to_owned()]
+            .into_iter()
+            .chain(forests)
+            .chain(rectangles)
+        {
+            let grid: Grid = input.parse().unwrap();
+            assert_eq!(count_visible(&grid), count_visible_naive(&grid));
+            assert_eq!(max_scenic_score(&grid), max_scenic_score_naive(&grid));
+        }
+    }
+
+    #[test]
+    fn maps_work() {
+        let grid: Grid = INPUT_TEST.parse().unwrap();
+
+        let visibility = grid.visibility();
+        let rows = (0..grid.rows()).map(|y| visibility.row(y).iter().map(|&v| v as u8));
+        let rows = rows.map(|row| row.collect::<Vec<_>>()).collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            [
+                [1, 1, 1, 1, 1],
+                [1, 1, 1, 0, 1],
+                [1, 1, 0, 1, 1],
+                [1, 0, 1, 0, 1],
+                [1, 1, 1, 1, 1],
+            ]
+        );
+
+        let scores = grid.scenic_scores();
+        assert_eq!(scores[(2, 1)], 4);
+        assert_eq!(scores[(2, 3)], 8);
+
+        let grid: Grid = "30373\n25512\n".parse().unwrap();
+        assert_eq!((grid.cols(), grid.rows()), (5, 2));
+        assert_eq!(grid[(4, 1)], 2);
+        assert_eq!(grid.get((1, 4)), None);
+        assert_eq!(count_visible(&grid), 10);
+        assert_eq!(max_scenic_score(&grid), 0);
+
+        let grid: Grid = "303\n255\n653\n335\n353".parse().unwrap();
+        assert_eq!((grid.cols(), grid.rows()), (3, 5));
+        assert_eq!(grid.row(2), [6, 5, 3]);
+        assert_eq!(grid.scenic_scores()[(1, 2)], 2);
+
+        assert_eq!("303\n25\n".parse::<Grid>(), Err(()));
+        assert_eq!("3a3\n255\n".parse::<Grid>(), Err(()));
+    }
 }
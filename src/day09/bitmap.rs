@@ -0,0 +1,140 @@
+use std::iter;
+
+use super::Position;
+
+// Bits per word
+const BITS: i64 = u64::BITS as i64;
+
+/// A set of positions, stored as a dense bitmap which grows to fit whatever is inserted.
+#[derive(Debug, Clone, Default)]
+pub struct Bitmap {
+    words: Vec<u64>,
+    // The position of the first bit
+    origin: [i64; 2],
+    // The number of words in each row
+    cols: usize,
+    rows: usize,
+    len: usize,
+}
+
+impl Bitmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        match self.locate(position) {
+            Some((index, mask)) => self.words[index] & mask != 0,
+            None => false,
+        }
+    }
+
+    /// Adds a position, returning whether it was newly inserted.
+    pub fn insert(&mut self, position: Position) -> bool {
+        let (index, mask) = match self.locate(position) {
+            Some(location) => location,
+            None => {
+                self.grow(position);
+                self.locate(position).unwrap()
+            }
+        };
+
+        let word = &mut self.words[index];
+        let inserted = *word & mask == 0;
+        *word |= mask;
+        self.len += inserted as usize;
+        inserted
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Position> + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(move |(index, &word)| {
+                let x = self.origin[0] + (index % self.cols) as i64 * BITS;
+                let y = self.origin[1] + (index / self.cols) as i64;
+
+                // Clears the lowest set bit each time, until none are left
+                let words = iter::successors(Some(word), |&word| Some(word & word.wrapping_sub(1)));
+                words
+                    .take_while(|&word| word != 0)
+                    .map(move |word| [(x + word.trailing_zeros() as i64) as i32, y as i32])
+            })
+    }
+
+    // The index of the word holding a position and the mask of its bit, if it's within bounds
+    fn locate(&self, [x, y]: Position) -> Option<(usize, u64)> {
+        let x = usize::try_from(x as i64 - self.origin[0]).ok()?;
+        let y = usize::try_from(y as i64 - self.origin[1]).ok()?;
+
+        if x >= self.cols * BITS as usize || y >= self.rows {
+            return None;
+        }
+
+        Some((x / BITS as usize + y * self.cols, 1 << (x % BITS as usize)))
+    }
+
+    // Makes room for a position, at least doubling the size along each axis it's out of bounds.
+    // Columns are added in whole words, so the existing rows can be copied over as they are.
+    fn grow(&mut self, [x, y]: Position) {
+        let (x, y) = (x as i64, y as i64);
+
+        if self.words.is_empty() {
+            *self = Self {
+                words: vec![0; BITS as usize],
+                origin: [x - BITS / 2, y - BITS / 2],
+                cols: 1,
+                rows: BITS as usize,
+                len: 0,
+            };
+            return;
+        }
+
+        let [min_x, min_y] = self.origin;
+        let (max_x, max_y) = (min_x + self.cols as i64 * BITS, min_y + self.rows as i64);
+
+        let (mut left, mut right) = (0, 0);
+        if x < min_x {
+            left = self.cols.max(((min_x - x + BITS - 1) / BITS) as usize);
+        } else if x >= max_x {
+            right = self.cols.max(((x - max_x) / BITS + 1) as usize);
+        }
+
+        let (mut below, mut above) = (0, 0);
+        if y < min_y {
+            below = self.rows.max((min_y - y) as usize);
+        } else if y >= max_y {
+            above = self.rows.max((y - max_y + 1) as usize);
+        }
+
+        let cols = left + self.cols + right;
+        let rows = below + self.rows + above;
+        let mut words = vec![0; cols * rows];
+
+        for (y, row) in self.words.chunks_exact(self.cols).enumerate() {
+            let start = (below + y) * cols + left;
+            words[start..start + self.cols].copy_from_slice(row);
+        }
+
+        self.words = words;
+        self.origin = [min_x - left as i64 * BITS, min_y - below as i64];
+        self.cols = cols;
+        self.rows = rows;
+    }
+}
+
+impl Extend<Position> for Bitmap {
+    fn extend<I: IntoIterator<Item = Position>>(&mut self, iter: I) {
+        for position in iter {
+            self.insert(position);
+        }
+    }
+}
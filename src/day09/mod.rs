@@ -1,52 +1,210 @@
-use std::collections::HashSet;
-
-fn walk_snake<const N: usize>(input: &str) -> usize {
-    let commands = input.trim().lines().map(|line| {
-        let (dir, steps) = line.split_once(' ').unwrap();
-        (dir.chars().next().unwrap(), steps.parse().unwrap())
-    });
-
-    let mut snake = [[0i32; 2]; N];
-    let mut visited = HashSet::new();
-
-    for (dir, steps) in commands {
-        for _ in 0..steps {
-            snake[0] = {
-                let [hx, hy] = snake[0];
-                match dir {
-                    'R' => [hx + 1, hy],
-                    'L' => [hx - 1, hy],
-                    'U' => [hx, hy + 1],
-                    'D' => [hx, hy - 1],
-                    _ => unreachable!(),
-                }
-            };
-
-            for i in 0..(N - 1) {
-                let [hx, hy] = snake[i];
-                let [tx, ty] = snake[i + 1];
-                let [dx, dy] = [hx - tx, hy - ty];
-
-                if dx.abs() > 1 || dy.abs() > 1 {
-                    snake[i + 1] = [tx + dx.signum(), ty + dy.signum()];
-                }
-            }
+use std::{array, cmp::Reverse, collections::HashSet, iter, str::FromStr};
+
+mod bitmap;
+
+pub use bitmap::Bitmap;
+
+pub type Position<const D: usize = 2> = [i32; D];
+
+/// Moves the head `steps` times by `delta`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Motion<const D: usize = 2> {
+    pub delta: Position<D>,
+    pub steps: u32,
+}
+
+// The axis and sign of each direction letter
+const DIRECTIONS: [(char, usize, i32); 6] = [
+    ('R', 0, 1),
+    ('L', 0, -1),
+    ('U', 1, 1),
+    ('D', 1, -1),
+    ('F', 2, 1),
+    ('B', 2, -1),
+];
+
+/// Parses a direction like `R`, `UR` or `DLF`, with at most one letter per axis.
+fn parse_direction<const D: usize>(s: &str) -> Result<Position<D>, ()> {
+    let mut delta = [0; D];
+
+    for c in s.chars() {
+        let &(_, axis, sign) = DIRECTIONS
+            .iter()
+            .find(|(letter, ..)| *letter == c)
+            .ok_or(())?;
+        match delta.get_mut(axis) {
+            Some(component) if *component == 0 => *component = sign,
+            _ => return Err(()),
+        }
+    }
+
+    if delta == [0; D] {
+        return Err(());
+    }
+
+    Ok(delta)
+}
+
+impl<const D: usize> FromStr for Motion<D> {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (direction, steps) = s.split_once(' ').ok_or(())?;
+
+        Ok(Motion {
+            delta: parse_direction(direction)?,
+            steps: steps.parse().map_err(|_| ())?,
+        })
+    }
+}
+
+pub trait Follow {
+    /// Where a knot moves to after the knot in front of it has moved to `leader`.
+    fn follow<const D: usize>(&self, leader: Position<D>, knot: Position<D>) -> Position<D>;
+}
 
-            if let Some(&tail_pos) = snake.last() {
-                visited.insert(tail_pos);
+/// Knots touch when they are adjacent, even diagonally, and a knot that falls behind moves one
+/// step towards the leader along every axis it is off by.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chebyshev;
+
+impl Follow for Chebyshev {
+    fn follow<const D: usize>(&self, leader: Position<D>, knot: Position<D>) -> Position<D> {
+        let delta: Position<D> = array::from_fn(|i| leader[i] - knot[i]);
+
+        if delta.iter().any(|d| d.abs() > 1) {
+            array::from_fn(|i| knot[i] + delta[i].signum())
+        } else {
+            knot
+        }
+    }
+}
+
+/// Knots touch when they share a side, and a knot that falls behind moves towards the leader
+/// along the axis it is furthest off by, one step at a time, until they touch again.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Manhattan;
+
+impl Follow for Manhattan {
+    fn follow<const D: usize>(&self, leader: Position<D>, mut knot: Position<D>) -> Position<D> {
+        loop {
+            let delta: Position<D> = array::from_fn(|i| leader[i] - knot[i]);
+
+            if delta.iter().map(|d| d.abs()).sum::<i32>() <= 1 {
+                return knot;
             }
+
+            let axis = (0..D)
+                .max_by_key(|&i| (delta[i].abs(), Reverse(i)))
+                .unwrap();
+            knot[axis] += delta[axis].signum();
         }
     }
+}
+
+/// A rope of knots, each following the one before it by the rule `R`, all starting out at the
+/// origin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rope<R = Chebyshev, const D: usize = 2> {
+    knots: Box<[Position<D>]>,
+    rule: R,
+}
+
+impl Rope {
+    pub fn new(len: usize) -> Self {
+        Self::with_rule(len, Chebyshev)
+    }
+}
+
+impl<R: Follow, const D: usize> Rope<R, D> {
+    pub fn with_rule(len: usize, rule: R) -> Self {
+        assert!(len > 0, "a rope needs at least one knot");
+
+        Self {
+            knots: vec![[0; D]; len].into(),
+            rule,
+        }
+    }
+
+    /// The position of every knot, starting with the head.
+    pub fn knots(&self) -> &[Position<D>] {
+        &self.knots
+    }
 
+    /// Moves the head one step by `delta`, dragging the rest of the knots along.
+    pub fn step(&mut self, delta: Position<D>) {
+        let head = &mut self.knots[0];
+        *head = array::from_fn(|i| head[i] + delta[i]);
+
+        for i in 1..self.knots.len() {
+            self.knots[i] = self.rule.follow(self.knots[i - 1], self.knots[i]);
+        }
+    }
+
+    /// Applies the motions, yielding the position of every knot after each step.
+    pub fn trail(
+        mut self,
+        motions: impl IntoIterator<Item = Motion<D>>,
+    ) -> impl Iterator<Item = Box<[Position<D>]>> {
+        steps(motions).map(move |delta| {
+            self.step(delta);
+            self.knots.clone()
+        })
+    }
+
+    /// Every position visited by the knot at `index`, counting from the head, while applying
+    /// the motions.
+    pub fn visited(
+        self,
+        index: usize,
+        motions: impl IntoIterator<Item = Motion<D>>,
+    ) -> HashSet<Position<D>> {
+        let mut visited = HashSet::new();
+        self.visit(index, motions, &mut visited);
+        visited
+    }
+
+    /// Like [`Rope::visited`], but records the positions in any collection.
+    pub fn visit(
+        mut self,
+        index: usize,
+        motions: impl IntoIterator<Item = Motion<D>>,
+        visited: &mut impl Extend<Position<D>>,
+    ) {
+        visited.extend([self.knots[index]]);
+
+        for delta in steps(motions) {
+            self.step(delta);
+            visited.extend([self.knots[index]]);
+        }
+    }
+}
+
+// Splits the motions into single steps
+fn steps<const D: usize>(
+    motions: impl IntoIterator<Item = Motion<D>>,
+) -> impl Iterator<Item = Position<D>> {
+    motions
+        .into_iter()
+        .flat_map(|motion| iter::repeat(motion.delta).take(motion.steps as usize))
+}
+
+pub fn parse_input<const D: usize>(input: &str) -> impl Iterator<Item = Motion<D>> + '_ {
+    input.trim().lines().map(|line| line.parse().unwrap())
+}
+
+fn count_visited(input: &str, len: usize) -> usize {
+    let mut visited = Bitmap::new();
+    Rope::new(len).visit(len - 1, parse_input(input), &mut visited);
     visited.len()
 }
 
 pub fn part1(input: &str) -> usize {
-    walk_snake::<2>(input)
+    count_visited(input, 2)
 }
 
 pub fn part2(input: &str) -> usize {
-    walk_snake::<10>(input)
+    count_visited(input, 10)
 }
 
 #[cfg(test)]
@@ -67,4 +225,107 @@ mod tests {
         assert_eq!(part2("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20"), 36);
         assert_eq!(part2(INPUT), 2607);
     }
+
+    #[test]
+    fn rope_works() {
+        let motions = parse_input("R 4\nU 4").collect::<Vec<_>>();
+
+        let trail = Rope::new(3).trail(motions.clone()).collect::<Vec<_>>();
+        assert_eq!(trail.len(), 8);
+        assert_eq!(&*trail[3], [[4, 0], [3, 0], [2, 0]]);
+        assert_eq!(&*trail[7], [[4, 4], [4, 3], [4, 2]]);
+
+        let visited = Rope::new(3).visited(2, motions.clone());
+        assert_eq!(visited.len(), 5);
+        assert!(visited.contains(&[3, 1]));
+
+        let visited = Rope::new(1).visited(0, motions);
+        assert_eq!(visited.len(), 9);
+
+        let coverage = (1..=10)
+            .map(|len| Rope::new(len).visited(len - 1, parse_input(INPUT)).len())
+            .collect::<Vec<_>>();
+        assert!(coverage.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert_eq!(coverage[1], 6376);
+        assert_eq!(coverage[9], 2607);
+    }
+
+    #[test]
+    fn motions_work() {
+        let motion = |s: &str| s.parse::<Motion>();
+        assert_eq!(
+            motion("UR 2"),
+            Ok(Motion {
+                delta: [1, 1],
+                steps: 2
+            })
+        );
+        assert_eq!(
+            motion("LD 1"),
+            Ok(Motion {
+                delta: [-1, -1],
+                steps: 1
+            })
+        );
+        assert_eq!(motion("UD 1"), Err(()));
+        assert_eq!(motion("X 1"), Err(()));
+        assert_eq!(motion("F 1"), Err(()));
+        assert_eq!("DF 4".parse::<Motion<3>>().map(|m| m.delta), Ok([0, -1, 1]));
+
+        let trail = Rope::new(2).trail(parse_input("UR 3")).last().unwrap();
+        assert_eq!(&*trail, [[3, 3], [2, 2]]);
+
+        let rope = Rope::<_, 3>::with_rule(2, Chebyshev);
+        let visited = rope.visited(1, parse_input("F 3\nUR 1"));
+        assert_eq!(visited, HashSet::from([[0, 0, 0], [0, 0, 1], [0, 0, 2]]));
+    }
+
+    #[test]
+    fn follow_rules_work() {
+        let trail = Rope::with_rule(2, Manhattan).trail(parse_input("R 2\nU 1"));
+        assert_eq!(&*trail.last().unwrap(), [[2, 1], [2, 0]]);
+
+        let trail = Rope::new(2).trail(parse_input("R 2\nU 1"));
+        assert_eq!(&*trail.last().unwrap(), [[2, 1], [1, 0]]);
+
+        let trail = Rope::with_rule(2, Manhattan).trail(parse_input("R 1\nUR 1"));
+        assert_eq!(&*trail.last().unwrap(), [[2, 1], [2, 0]]);
+
+        let rope = Rope::with_rule(10, Manhattan);
+        let knots = rope.trail(parse_input(INPUT)).last().unwrap();
+        let touching = knots.windows(2).all(|pair| {
+            let [[hx, hy], [tx, ty]] = [pair[0], pair[1]];
+            (hx - tx).abs() + (hy - ty).abs() <= 1
+        });
+        assert!(touching);
+    }
+
+    #[test]
+    fn bitmap_works() {
+        let mut bitmap = Bitmap::new();
+        assert!(bitmap.is_empty() && !bitmap.contains([0, 0]));
+
+        let positions = [
+            [0, 0],
+            [-100, 3],
+            [63, -64],
+            [64, 64],
+            [-100, 3],
+            [1000, -1000],
+        ];
+        let inserted = positions.map(|position| bitmap.insert(position));
+        assert_eq!(inserted, [true, true, true, true, false, true]);
+        assert_eq!(bitmap.len(), 5);
+        assert!(positions.iter().all(|&position| bitmap.contains(position)));
+        assert!(!bitmap.contains([1, 0]) && !bitmap.contains([i32::MIN, i32::MAX]));
+
+        for len in [1, 2, 5, 10] {
+            let mut visited = Bitmap::new();
+            Rope::new(len).visit(len - 1, parse_input(INPUT), &mut visited);
+
+            let expected = Rope::new(len).visited(len - 1, parse_input(INPUT));
+            assert_eq!(visited.len(), expected.len());
+            assert_eq!(visited.iter().collect::<HashSet<_>>(), expected);
+        }
+    }
 }
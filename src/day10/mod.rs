@@ -1,9 +1,132 @@
-use std::str::FromStr;
+use std::{
+    fmt::{self, Display},
+    mem,
+    str::FromStr,
+};
 
-#[derive(Debug)]
-enum Instruction {
+use crate::ocr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    X,
+    Y,
+}
+
+/// What an instruction does once it completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
     Noop,
-    Addx(i32),
+    /// Adds the operand to a register.
+    Add(Register),
+    /// Sets a register to the operand.
+    Set(Register),
+    /// Swaps the values of the registers.
+    Swap,
+}
+
+/// An entry in the instruction table.
+#[derive(Debug)]
+pub struct Opcode {
+    pub mnemonic: &'static str,
+    pub op: Op,
+    /// Number of operands, either 0 or 1.
+    pub operands: usize,
+    pub cycles: usize,
+}
+
+pub static OPCODES: [Opcode; 6] = [
+    Opcode {
+        mnemonic: "noop",
+        op: Op::Noop,
+        operands: 0,
+        cycles: 1,
+    },
+    Opcode {
+        mnemonic: "addx",
+        op: Op::Add(Register::X),
+        operands: 1,
+        cycles: 2,
+    },
+    Opcode {
+        mnemonic: "addy",
+        op: Op::Add(Register::Y),
+        operands: 1,
+        cycles: 2,
+    },
+    Opcode {
+        mnemonic: "setx",
+        op: Op::Set(Register::X),
+        operands: 1,
+        cycles: 1,
+    },
+    Opcode {
+        mnemonic: "sety",
+        op: Op::Set(Register::Y),
+        operands: 1,
+        cycles: 1,
+    },
+    Opcode {
+        mnemonic: "swap",
+        op: Op::Swap,
+        operands: 0,
+        cycles: 1,
+    },
+];
+
+impl Opcode {
+    pub fn find(op: Op) -> &'static Opcode {
+        OPCODES.iter().find(|opcode| opcode.op == op).unwrap()
+    }
+
+    pub fn parse(mnemonic: &str) -> Option<&'static Opcode> {
+        OPCODES.iter().find(|opcode| opcode.mnemonic == mnemonic)
+    }
+}
+
+/// An operation with as many operands as its opcode takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    op: Op,
+    operand: Option<i32>,
+}
+
+impl Instruction {
+    /// Returns `None` if the number of operands doesn't match the opcode.
+    pub fn new(op: Op, operand: Option<i32>) -> Option<Self> {
+        let instr = Self { op, operand };
+        (instr.opcode().operands == operand.iter().count()).then_some(instr)
+    }
+
+    pub fn noop() -> Self {
+        Self {
+            op: Op::Noop,
+            operand: None,
+        }
+    }
+
+    pub fn addx(val: i32) -> Self {
+        Self {
+            op: Op::Add(Register::X),
+            operand: Some(val),
+        }
+    }
+
+    pub fn op(&self) -> Op {
+        self.op
+    }
+
+    pub fn operand(&self) -> Option<i32> {
+        self.operand
+    }
+
+    pub fn opcode(&self) -> &'static Opcode {
+        Opcode::find(self.op)
+    }
+
+    /// Number of cycles the instruction takes to complete.
+    pub fn cycles(&self) -> usize {
+        self.opcode().cycles
+    }
 }
 
 impl FromStr for Instruction {
@@ -12,85 +135,273 @@ impl FromStr for Instruction {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut it = s.split_whitespace();
 
-        Ok(match it.next().unwrap() {
-            "noop" => Instruction::Noop,
-            "addx" => Instruction::Addx(it.next().unwrap().parse().unwrap()),
-            _ => unreachable!(),
-        })
+        let opcode = Opcode::parse(it.next().ok_or(())?).ok_or(())?;
+
+        let operands = it
+            .map(|operand| operand.parse().map_err(|_| ()))
+            .collect::<Result<Vec<i32>, _>>()?;
+
+        if operands.len() > 1 {
+            return Err(());
+        }
+
+        Instruction::new(opcode.op, operands.first().copied()).ok_or(())
     }
 }
 
-pub fn part1(input: &str) -> i32 {
-    let instructions = input.trim().lines().map(|line| line.parse().unwrap());
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.opcode().mnemonic)?;
+        if let Some(operand) = self.operand {
+            write!(f, " {operand}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a program, skipping blank lines and `#` comments, or returns the number of the first
+/// invalid line.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, usize> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.split('#').next().unwrap().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(number, line)| line.parse().map_err(|_| number))
+        .collect()
+}
+
+/// Turns a program back into its source, one instruction per line.
+pub fn disassemble(program: &[Instruction]) -> String {
+    program.iter().map(|instr| format!("{instr}\n")).collect()
+}
+
+/// The state of the CPU during a cycle, counting from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    pub cycle: usize,
+    pub x: i32,
+    pub y: i32,
+}
 
-    let mut cycle_count = 0;
-    let mut total_signal_strength = 0;
+impl Tick {
+    pub fn signal_strength(&self) -> i32 {
+        self.cycle as i32 * self.x
+    }
+}
 
-    let mut increment_cycle = |x_register| {
-        cycle_count += 1;
+/// Executes a program, yielding the state of the CPU during each cycle.
+///
+/// Instructions take effect at the end of their last cycle.
+#[derive(Debug, Clone)]
+pub struct Cpu<I> {
+    program: I,
+    x: i32,
+    y: i32,
+    cycle: usize,
+    // The instruction being executed, with the number of cycles it has left
+    current: Option<(Instruction, usize)>,
+}
 
-        if (cycle_count + 20) % 40 == 0 {
-            total_signal_strength += cycle_count * (x_register + 1);
+impl<I: Iterator<Item = Instruction>> Cpu<I> {
+    pub fn new(program: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            program: program.into_iter(),
+            x: 1,
+            y: 0,
+            cycle: 0,
+            current: None,
         }
-    };
+    }
 
-    let mut x_register = 0;
+    pub fn x(&self) -> i32 {
+        self.x
+    }
 
-    for instr in instructions {
-        match instr {
-            Instruction::Noop => {
-                increment_cycle(x_register);
-            }
-            Instruction::Addx(val) => {
-                increment_cycle(x_register);
-                increment_cycle(x_register);
-                x_register += val;
-            }
+    pub fn register(&self, register: Register) -> i32 {
+        match register {
+            Register::X => self.x,
+            Register::Y => self.y,
         }
     }
 
-    total_signal_strength
+    fn register_mut(&mut self, register: Register) -> &mut i32 {
+        match register {
+            Register::X => &mut self.x,
+            Register::Y => &mut self.y,
+        }
+    }
+
+    /// Number of cycles completed so far.
+    pub fn cycles(&self) -> usize {
+        self.cycle
+    }
+
+    /// Runs until a cycle matching the breakpoint, returning its state, or until the program
+    /// ends.
+    pub fn run_until(&mut self, mut breakpoint: impl FnMut(&Tick) -> bool) -> Option<Tick> {
+        self.find(|tick| breakpoint(tick))
+    }
+
+    /// Runs until the given cycle, returning its state.
+    pub fn run_to(&mut self, cycle: usize) -> Option<Tick> {
+        self.run_until(|tick| tick.cycle >= cycle)
+    }
+
+    fn execute(&mut self, instr: Instruction) {
+        let operand = instr.operand.unwrap_or(0);
+
+        match instr.op {
+            Op::Noop => {}
+            Op::Add(register) => *self.register_mut(register) += operand,
+            Op::Set(register) => *self.register_mut(register) = operand,
+            Op::Swap => mem::swap(&mut self.x, &mut self.y),
+        }
+    }
 }
 
-pub fn part2(input: &str) -> String {
-    let instructions = input.trim().lines().map(|line| line.parse().unwrap());
+impl<I: Iterator<Item = Instruction>> Iterator for Cpu<I> {
+    type Item = Tick;
 
-    let mut output = String::with_capacity(240);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (instr, remaining) = match self.current.take() {
+            Some(current) => current,
+            None => {
+                let instr = self.program.next()?;
+                (instr, instr.cycles())
+            }
+        };
 
-    let mut cycle_count = 0;
+        self.cycle += 1;
 
-    let mut increment_cycle = |x_register| {
-        let col = cycle_count % 40;
+        let tick = Tick {
+            cycle: self.cycle,
+            x: self.x,
+            y: self.y,
+        };
 
-        if (x_register..x_register + 3).contains(&col) {
-            output.push('#');
+        if remaining > 1 {
+            self.current = Some((instr, remaining - 1));
         } else {
-            output.push('.');
+            self.execute(instr);
         }
 
-        if col == 39 {
+        Some(tick)
+    }
+}
+
+pub fn parse_input(input: &str) -> impl Iterator<Item = Instruction> + '_ {
+    input.trim().lines().map(|line| line.parse().unwrap())
+}
+
+pub fn part1(input: &str) -> i32 {
+    Cpu::new(parse_input(input))
+        .filter(|tick| (tick.cycle + 20) % 40 == 0)
+        .map(|tick| tick.signal_strength())
+        .sum()
+}
+
+const CRT_WIDTH: usize = 40;
+const CRT_HEIGHT: usize = 6;
+
+// Whether the sprite at `x` covers the pixel drawn during `cycle`
+fn is_lit(cycle: usize, x: i32) -> bool {
+    let col = ((cycle - 1) % CRT_WIDTH) as i32;
+    (x - 1..=x + 1).contains(&col)
+}
+
+/// Draws the CRT image, with `#` for lit pixels and `.` for dark ones.
+pub fn render(input: &str) -> String {
+    let mut output = String::with_capacity((CRT_WIDTH + 1) * CRT_HEIGHT);
+
+    for tick in Cpu::new(parse_input(input)) {
+        output.push(if is_lit(tick.cycle, tick.x) { '#' } else { '.' });
+
+        if tick.cycle % CRT_WIDTH == 0 {
             output.push('\n');
         }
+    }
 
-        cycle_count += 1;
-    };
+    output
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompileError {
+    /// The image isn't 6 rows of 40 `#` and `.` pixels.
+    Malformed,
+    /// No program of `noop` and `addx` instructions draws the image.
+    Impossible,
+}
+
+/// Finds a program of `noop` and `addx` instructions which makes the CRT draw the image.
+pub fn compile(image: &str) -> Result<Vec<Instruction>, CompileError> {
+    let rows = image.lines().collect::<Vec<_>>();
+
+    let well_formed = rows.len() == CRT_HEIGHT
+        && rows
+            .iter()
+            .all(|row| row.len() == CRT_WIDTH && row.bytes().all(|pixel| b"#.".contains(&pixel)));
+
+    if !well_formed {
+        return Err(CompileError::Malformed);
+    }
 
-    let mut x_register = 0;
+    let pixels = rows
+        .concat()
+        .bytes()
+        .map(|pixel| pixel == b'#')
+        .collect::<Vec<_>>();
+    let cycles = pixels.len();
 
-    for instr in instructions {
-        match instr {
-            Instruction::Noop => {
-                increment_cycle(x_register);
+    // Sprite positions worth considering, since anything further out lights nothing
+    let (min_x, max_x) = (-2, CRT_WIDTH as i32 + 1);
+    let positions = (max_x - min_x + 1) as usize;
+
+    // For each cycle boundary and sprite position, the position at the previous boundary and the
+    // instruction that led there, if it can be reached while drawing every pixel so far
+    let mut reached = vec![vec![None; positions]; cycles + 1];
+    reached[0][(1 - min_x) as usize] = Some((1, Instruction::noop()));
+
+    let matches = |cycle: usize, x: i32| is_lit(cycle, x) == pixels[cycle - 1];
+
+    for boundary in 0..cycles {
+        for x in min_x..=max_x {
+            if reached[boundary][(x - min_x) as usize].is_none() || !matches(boundary + 1, x) {
+                continue;
             }
-            Instruction::Addx(val) => {
-                increment_cycle(x_register);
-                increment_cycle(x_register);
-                x_register += val;
+
+            reached[boundary + 1][(x - min_x) as usize].get_or_insert((x, Instruction::noop()));
+
+            if boundary + 2 <= cycles && matches(boundary + 2, x) {
+                for next in min_x..=max_x {
+                    let instr = Instruction::addx(next - x);
+                    reached[boundary + 2][(next - min_x) as usize].get_or_insert((x, instr));
+                }
             }
         }
     }
 
-    output
+    let mut x = (min_x..=max_x)
+        .find(|&x| reached[cycles][(x - min_x) as usize].is_some())
+        .ok_or(CompileError::Impossible)?;
+
+    let mut program = Vec::new();
+    let mut boundary = cycles;
+
+    while boundary > 0 {
+        let (previous, instr) = reached[boundary][(x - min_x) as usize].unwrap();
+        program.push(instr);
+        boundary -= instr.cycles();
+        x = previous;
+    }
+
+    program.reverse();
+
+    Ok(program)
+}
+
+pub fn part2(input: &str) -> String {
+    ocr::recognize(&render(input)).unwrap()
 }
 
 #[cfg(test)]
@@ -108,8 +419,13 @@ mod tests {
 
     #[test]
     fn part2_works() {
+        assert_eq!(part2(INPUT), "BJFRHRFU");
+    }
+
+    #[test]
+    fn render_works() {
         assert_eq!(
-            part2(INPUT_TEST),
+            render(INPUT_TEST),
             concat!(
                 "##..##..##..##..##..##..##..##..##..##..\n",
                 "###...###...###...###...###...###...###.\n",
@@ -120,7 +436,7 @@ mod tests {
             )
         );
         assert_eq!(
-            part2(INPUT),
+            render(INPUT),
             concat!(
                 "###....##.####.###..#..#.###..####.#..#.\n",
                 "#..#....#.#....#..#.#..#.#..#.#....#..#.\n",
@@ -130,5 +446,112 @@ mod tests {
                 "###...##..#....#..#.#..#.#..#.#.....##..\n"
             )
         );
+        assert_eq!(
+            ocr::recognize(&render(INPUT_TEST)),
+            Err(ocr::Error::UnknownGlyph(0))
+        );
+    }
+
+    #[test]
+    fn cpu_works() {
+        let program = parse_input("noop\naddx 3\naddx -5");
+
+        let mut cpu = Cpu::new(program);
+        let ticks = cpu
+            .by_ref()
+            .map(|tick| (tick.cycle, tick.x))
+            .collect::<Vec<_>>();
+        assert_eq!(ticks, [(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        assert_eq!((cpu.cycles(), cpu.x()), (5, -1));
+
+        let mut cpu = Cpu::new(parse_input(INPUT_TEST));
+        assert_eq!(
+            cpu.run_to(20),
+            Some(Tick {
+                cycle: 20,
+                x: 21,
+                y: 0
+            })
+        );
+        assert_eq!(cpu.run_to(60).unwrap().signal_strength(), 1140);
+
+        let tick = cpu.run_until(|tick| tick.x < 0);
+        assert!(tick.map_or(false, |tick| tick.cycle > 60 && tick.x < 0));
+        assert_eq!(cpu.run_until(|tick| tick.x > 1000), None);
+        assert_eq!(cpu.cycles(), 240);
+
+        assert_eq!("addx".parse::<Instruction>(), Err(()));
+        assert_eq!("mulx 2".parse::<Instruction>(), Err(()));
+        assert_eq!("noop 2".parse::<Instruction>(), Err(()));
+    }
+
+    #[test]
+    fn assembler_works() {
+        let source = "# Counts up in y\nsety 10\n\naddy -3 # three down\nswap\naddx 1\nnoop\n";
+
+        let program = assemble(source).unwrap();
+        assert_eq!(program.len(), 5);
+        assert_eq!(program[3], Instruction::addx(1));
+        assert_eq!(program[4], Instruction::noop());
+        assert_eq!(
+            disassemble(&program),
+            "sety 10\naddy -3\nswap\naddx 1\nnoop\n"
+        );
+
+        let ticks = Cpu::new(program)
+            .map(|tick| (tick.x, tick.y))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ticks,
+            [(1, 0), (1, 10), (1, 10), (1, 7), (7, 1), (7, 1), (8, 1)]
+        );
+
+        let program = assemble(INPUT).unwrap();
+        assert_eq!(disassemble(&program), INPUT);
+
+        assert_eq!(assemble("noop\naddx\n"), Err(2));
+        assert_eq!(assemble("noop\n\nswap 1\n"), Err(3));
+        assert_eq!(assemble("addz 1"), Err(1));
+        assert_eq!(assemble("addx 1 2"), Err(1));
+
+        let add = Instruction::new(Op::Add(Register::Y), Some(2)).unwrap();
+        assert_eq!((add.op(), add.operand()), (Op::Add(Register::Y), Some(2)));
+        assert_eq!(Instruction::new(Op::Add(Register::X), None), None);
+        assert_eq!(Instruction::new(Op::Swap, Some(1)), None);
+        assert_eq!(Instruction::new(Op::Noop, None), Some(Instruction::noop()));
+    }
+
+    #[test]
+    fn compile_works() {
+        for image in [render(INPUT), render(INPUT_TEST)] {
+            let program = compile(&image).unwrap();
+            assert_eq!(render(&disassemble(&program)), image);
+        }
+
+        let letters = concat!(
+            "###..#....#..#.####.#..#.####.###...###.\n",
+            "#..#.#....#..#.#....#..#.#....#..#.#....\n",
+            "###..#....#..#.###..####.###..#..#.#....\n",
+            "#..#.#....#..#.#....#..#.#....###...##..\n",
+            "#..#.#....#..#.#....#..#.#....#.#.....#.\n",
+            "###..####..##..####.#..#.####.#..#.###..\n",
+        );
+        let program = compile(letters).unwrap();
+        assert_eq!(part2(&disassemble(&program)), "BLUEHERS");
+
+        // The sprite starts out covering the first pixel, and can't move before the third one
+        let dark = ".".repeat(CRT_WIDTH) + "\n";
+        assert_eq!(compile(&dark.repeat(6)), Err(CompileError::Impossible));
+        let gap = "#.#".to_owned() + &dark[3..];
+        assert_eq!(
+            compile(&(gap + &dark.repeat(5))),
+            Err(CompileError::Impossible)
+        );
+
+        assert_eq!(compile(&dark.repeat(5)), Err(CompileError::Malformed));
+        assert_eq!(
+            compile(&letters.replace('.', " ")),
+            Err(CompileError::Malformed)
+        );
     }
 }
@@ -0,0 +1,164 @@
+use std::{
+    iter::{self, Peekable},
+    str::FromStr,
+    vec,
+};
+
+/// An arithmetic expression over the old worry level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Const(u128),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalError {
+    Overflow,
+    /// A subtraction went below zero.
+    Underflow,
+    DivisionByZero,
+}
+
+impl Expr {
+    pub fn eval(&self, old: u128) -> Result<u128, EvalError> {
+        match self {
+            Expr::Old => Ok(old),
+            Expr::Const(val) => Ok(*val),
+            Expr::Add(a, b) => (a.eval(old)?.checked_add(b.eval(old)?)).ok_or(EvalError::Overflow),
+            Expr::Sub(a, b) => (a.eval(old)?.checked_sub(b.eval(old)?)).ok_or(EvalError::Underflow),
+            Expr::Mul(a, b) => (a.eval(old)?.checked_mul(b.eval(old)?)).ok_or(EvalError::Overflow),
+            Expr::Div(a, b) => {
+                (a.eval(old)?.checked_div(b.eval(old)?)).ok_or(EvalError::DivisionByZero)
+            }
+        }
+    }
+
+    /// Whether the expression only adds and multiplies, so that evaluating it on a worry level
+    /// reduced modulo some number gives the result reduced modulo the same number.
+    pub fn is_polynomial(&self) -> bool {
+        match self {
+            Expr::Old | Expr::Const(_) => true,
+            Expr::Add(a, b) | Expr::Mul(a, b) => a.is_polynomial() && b.is_polynomial(),
+            Expr::Sub(..) | Expr::Div(..) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Number(u128),
+    Name(&'a str),
+    Symbol(char),
+}
+
+fn tokens(s: &str) -> impl Iterator<Item = Result<Token<'_>, ()>> + '_ {
+    let mut rest = s;
+
+    iter::from_fn(move || {
+        rest = rest.trim_start();
+        let c = rest.chars().next()?;
+
+        let len = if c.is_ascii_alphanumeric() {
+            rest.find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len())
+        } else {
+            c.len_utf8()
+        };
+
+        let (token, tail) = rest.split_at(len);
+        rest = tail;
+
+        Some(if c.is_ascii_digit() {
+            token.parse().map(Token::Number).map_err(|_| ())
+        } else if c.is_ascii_alphabetic() {
+            Ok(Token::Name(token))
+        } else {
+            Ok(Token::Symbol(c))
+        })
+    })
+}
+
+// A recursive descent parser, where each level binds tighter than the one before
+struct Parser<'a> {
+    tokens: Peekable<vec::IntoIter<Token<'a>>>,
+}
+
+impl Parser<'_> {
+    // Consumes the next token if it's one of the given symbols
+    fn symbol(&mut self, symbols: &[char]) -> Option<char> {
+        match self.tokens.peek() {
+            Some(&Token::Symbol(c)) if symbols.contains(&c) => {
+                self.tokens.next();
+                Some(c)
+            }
+            _ => None,
+        }
+    }
+
+    // expr = term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr, ()> {
+        let mut expr = self.term()?;
+
+        while let Some(op) = self.symbol(&['+', '-']) {
+            let (a, b) = (Box::new(expr), Box::new(self.term()?));
+            expr = match op {
+                '+' => Expr::Add(a, b),
+                _ => Expr::Sub(a, b),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // term = factor (('*' | '/') factor)*
+    fn term(&mut self) -> Result<Expr, ()> {
+        let mut expr = self.factor()?;
+
+        while let Some(op) = self.symbol(&['*', '/']) {
+            let (a, b) = (Box::new(expr), Box::new(self.factor()?));
+            expr = match op {
+                '*' => Expr::Mul(a, b),
+                _ => Expr::Div(a, b),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // factor = number | 'old' | '(' expr ')'
+    fn factor(&mut self) -> Result<Expr, ()> {
+        match self.tokens.next().ok_or(())? {
+            Token::Number(val) => Ok(Expr::Const(val)),
+            Token::Name("old") => Ok(Expr::Old),
+            Token::Symbol('(') => {
+                let expr = self.expr()?;
+                self.symbol(&[')']).ok_or(())?;
+                Ok(expr)
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokens(s).collect::<Result<Vec<_>, _>>()?;
+
+        let mut parser = Parser {
+            tokens: tokens.into_iter().peekable(),
+        };
+
+        let expr = parser.expr()?;
+
+        match parser.tokens.next() {
+            Some(_) => Err(()),
+            None => Ok(expr),
+        }
+    }
+}
@@ -1,20 +1,17 @@
-use std::mem;
+use std::{collections::HashMap, mem};
+
+mod expr;
+
+pub use expr::{EvalError, Expr};
 
 #[derive(Debug, Clone)]
 struct Monkey {
     items: Vec<u64>,
-    operation: Operation,
+    operation: Expr,
     test: Test,
     total_inspections: usize,
 }
 
-#[derive(Debug, Clone)]
-enum Operation {
-    Add(u64),
-    Multiply(u64),
-    Square,
-}
-
 #[derive(Debug, Clone)]
 struct Test {
     divisible_by: u64,
@@ -40,17 +37,11 @@ fn iter_monkeys(input: &str) -> impl Iterator<Item = Monkey> + '_ {
 
         let operation = {
             let line = lines.next().unwrap();
-            let op = line.trim_start().strip_prefix("Operation:").unwrap().trim();
-
-            if op == "new = old * old" {
-                Operation::Square
-            } else if let Some(amount) = op.strip_prefix("new = old * ") {
-                Operation::Multiply(amount.parse().unwrap())
-            } else if let Some(amount) = op.strip_prefix("new = old + ") {
-                Operation::Add(amount.parse().unwrap())
-            } else {
-                unreachable!()
-            }
+            line.trim_start()
+                .strip_prefix("Operation: new =")
+                .unwrap()
+                .parse()
+                .unwrap()
         };
 
         let test = {
@@ -100,106 +91,316 @@ fn iter_monkeys(input: &str) -> impl Iterator<Item = Monkey> + '_ {
     })
 }
 
-pub fn part1(input: &str) -> usize {
-    let monkeys = iter_monkeys(input);
+/// How worry levels are kept in check after each inspection.
+///
+/// Worry levels are computed with 128 bits, so they can be squared before being relieved.
+pub trait Relief {
+    fn relieve(&self, worry_level: u128) -> u128;
+
+    /// Whether the relief gives the right results for an operation.
+    fn supports(&self, _operation: &Expr) -> bool {
+        true
+    }
+}
 
-    let mut monkeys = monkeys.collect::<Vec<_>>();
+/// Worry levels are divided by three, rounding down.
+#[derive(Debug, Clone, Copy)]
+pub struct Divide;
 
-    for _ in 0..20 {
-        for monkey_index in 0..monkeys.len() {
-            let items = mem::take(&mut monkeys[monkey_index].items);
+impl Relief for Divide {
+    fn relieve(&self, worry_level: u128) -> u128 {
+        worry_level / 3
+    }
+}
 
-            monkeys[monkey_index].total_inspections += items.len();
+/// Worry levels are kept modulo a multiple of every divisor, which preserves all the tests as
+/// long as operations only add and multiply.
+#[derive(Debug, Clone, Copy)]
+pub struct Modulo(pub u64);
+
+impl Relief for Modulo {
+    fn relieve(&self, worry_level: u128) -> u128 {
+        worry_level % self.0 as u128
+    }
+
+    fn supports(&self, operation: &Expr) -> bool {
+        operation.is_polynomial()
+    }
+}
+
+/// The items held by each monkey and how many items each has inspected, after some rounds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub round: usize,
+    pub items: Vec<Vec<u64>>,
+    pub inspections: Vec<usize>,
+}
+
+impl Snapshot {
+    /// The product of the two highest inspection counts.
+    pub fn monkey_business(&self) -> usize {
+        monkey_business(&self.inspections)
+    }
+}
+
+/// The product of the two highest inspection counts.
+pub fn monkey_business(inspections: &[usize]) -> usize {
+    let mut inspections = inspections.to_vec();
+    inspections.sort_unstable();
+    inspections.iter().rev().take(2).product()
+}
+
+/// Plays out rounds of monkeys throwing items, yielding a snapshot after each one.
+#[derive(Debug, Clone)]
+pub struct Simulation<R> {
+    monkeys: Vec<Monkey>,
+    relief: R,
+    round: usize,
+    rounds: usize,
+    failed: bool,
+}
+
+/// A monkey's operation failed on an item, or its worry level grew too large to keep track of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InspectionError {
+    pub round: usize,
+    pub monkey: usize,
+    pub item: u64,
+    pub cause: EvalError,
+}
+
+/// The operation of the given monkey isn't supported by the relief.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unsupported {
+    pub monkey: usize,
+}
+
+impl<R: Relief> Simulation<R> {
+    pub fn new(input: &str, relief: R, rounds: usize) -> Result<Self, Unsupported> {
+        let monkeys = iter_monkeys(input).collect::<Vec<_>>();
+
+        if let Some(monkey) = monkeys
+            .iter()
+            .position(|monkey| !relief.supports(&monkey.operation))
+        {
+            return Err(Unsupported { monkey });
+        }
+
+        Ok(Self {
+            monkeys,
+            relief,
+            round: 0,
+            rounds,
+            failed: false,
+        })
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            round: self.round,
+            items: self
+                .monkeys
+                .iter()
+                .map(|monkey| monkey.items.clone())
+                .collect(),
+            inspections: self
+                .monkeys
+                .iter()
+                .map(|monkey| monkey.total_inspections)
+                .collect(),
+        }
+    }
+
+    fn play_round(&mut self) -> Result<(), InspectionError> {
+        self.round += 1;
+
+        for monkey_index in 0..self.monkeys.len() {
+            let items = mem::take(&mut self.monkeys[monkey_index].items);
+
+            self.monkeys[monkey_index].total_inspections += items.len();
 
             for item in items {
-                let worry_level = match monkeys[monkey_index].operation {
-                    Operation::Add(val) => item + val,
-                    Operation::Multiply(val) => item * val,
-                    Operation::Square => item * item,
-                };
-
-                let worry_level = worry_level / 3;
-
-                let Test {
-                    divisible_by,
-                    monkey_if_false,
-                    monkey_if_true,
-                } = monkeys[monkey_index].test;
-
-                let target_monkey_index = if worry_level % divisible_by == 0 {
-                    monkey_if_true
-                } else {
-                    monkey_if_false
-                };
-
-                monkeys[target_monkey_index].items.push(worry_level)
+                let (target_monkey_index, worry_level) =
+                    self.inspect_item(monkey_index, item, self.round)?;
+                self.monkeys[target_monkey_index].items.push(worry_level)
             }
         }
+
+        Ok(())
     }
 
-    monkeys.sort_unstable_by_key(|monkey| monkey.total_inspections);
+    // Inspects an item, returning which monkey it's thrown to and its new worry level
+    fn inspect_item(
+        &self,
+        monkey_index: usize,
+        item: u64,
+        round: usize,
+    ) -> Result<(usize, u64), InspectionError> {
+        let error = |cause| InspectionError {
+            round,
+            monkey: monkey_index,
+            item,
+            cause,
+        };
 
-    monkeys
-        .into_iter()
-        .rev()
-        .take(2)
-        .map(|monkey| monkey.total_inspections)
-        .product()
-}
+        let monkey = &self.monkeys[monkey_index];
 
-pub fn part2(input: &str) -> usize {
-    let monkeys = iter_monkeys(input);
+        let worry_level = monkey.operation.eval(item.into()).map_err(error)?;
+        let worry_level = u64::try_from(self.relief.relieve(worry_level))
+            .map_err(|_| error(EvalError::Overflow))?;
 
-    let mut monkeys = monkeys.collect::<Vec<_>>();
+        let Test {
+            divisible_by,
+            monkey_if_false,
+            monkey_if_true,
+        } = monkey.test;
 
-    let lowest_common_multiple = monkeys
-        .iter()
-        .map(|monkey| monkey.test.divisible_by)
-        .product::<u64>();
+        let target_monkey_index = if worry_level % divisible_by == 0 {
+            monkey_if_true
+        } else {
+            monkey_if_false
+        };
+
+        Ok((target_monkey_index, worry_level))
+    }
+
+    /// Plays out the remaining rounds, returning the final snapshot.
+    pub fn run(mut self) -> Result<Snapshot, InspectionError> {
+        while self.round < self.rounds {
+            self.play_round()?;
+        }
+        Ok(self.snapshot())
+    }
+}
+
+impl Simulation<Modulo> {
+    /// The inspection counts after `rounds` more rounds, without playing each one of them.
+    ///
+    /// Items move independently, and with worry levels kept modulo some number each one can only
+    /// be in finitely many states, so its path from round to round eventually repeats. Each item
+    /// is followed until it's back in a state it's been in before, after which the cycle can be
+    /// counted as a whole.
+    pub fn fast_forward(&self, rounds: usize) -> Result<Vec<usize>, InspectionError> {
+        let mut inspections = self
+            .monkeys
+            .iter()
+            .map(|monkey| monkey.total_inspections)
+            .collect::<Vec<_>>();
+
+        for (monkey_index, monkey) in self.monkeys.iter().enumerate() {
+            for &item in &monkey.items {
+                let counts = self.fast_forward_item((monkey_index, item), rounds)?;
+                for (total, count) in inspections.iter_mut().zip(counts) {
+                    *total += count;
+                }
+            }
+        }
 
-    let mut items = Vec::new();
+        Ok(inspections)
+    }
 
-    for _ in 0..10_000 {
-        for monkey_index in 0..monkeys.len() {
-            items.clear();
-            mem::swap(&mut items, &mut monkeys[monkey_index].items);
+    // The inspections of a single item, starting out with the given holder and worry level
+    fn fast_forward_item(
+        &self,
+        start: (usize, u64),
+        rounds: usize,
+    ) -> Result<Vec<usize>, InspectionError> {
+        // The round each state was first seen at the start of, and the inspections before each round
+        let mut seen = HashMap::from([(start, 0)]);
+        let mut totals = vec![vec![0; self.monkeys.len()]];
+        let mut state = start;
+
+        for round in 1..=rounds {
+            let mut counts = totals[round - 1].clone();
+            state = self.play_item_round(state, self.round + round, &mut counts)?;
+            totals.push(counts);
+
+            if let Some(first) = seen.insert(state, round) {
+                let len = round - first;
+                let (cycles, rest) = ((rounds - first) / len, (rounds - first) % len);
+
+                return Ok((0..self.monkeys.len())
+                    .map(|i| {
+                        totals[first + rest][i] + cycles * (totals[round][i] - totals[first][i])
+                    })
+                    .collect());
+            }
+        }
 
-            monkeys[monkey_index].total_inspections += items.len();
+        Ok(totals.pop().unwrap())
+    }
 
-            for item in items.iter() {
-                let worry_level = match monkeys[monkey_index].operation {
-                    Operation::Add(val) => item + val,
-                    Operation::Multiply(val) => item * val,
-                    Operation::Square => item * item,
-                };
+    // Follows an item through one round, until it's thrown to a monkey whose turn has passed
+    fn play_item_round(
+        &self,
+        (mut monkey_index, mut item): (usize, u64),
+        round: usize,
+        inspections: &mut [usize],
+    ) -> Result<(usize, u64), InspectionError> {
+        loop {
+            inspections[monkey_index] += 1;
+
+            let (target_monkey_index, worry_level) =
+                self.inspect_item(monkey_index, item, round)?;
+
+            if target_monkey_index <= monkey_index {
+                return Ok((target_monkey_index, worry_level));
+            }
 
-                let worry_level = worry_level % lowest_common_multiple;
+            (monkey_index, item) = (target_monkey_index, worry_level);
+        }
+    }
+}
 
-                let Test {
-                    divisible_by,
-                    monkey_if_false,
-                    monkey_if_true,
-                } = monkeys[monkey_index].test;
+impl<R: Relief> Iterator for Simulation<R> {
+    type Item = Result<Snapshot, InspectionError>;
 
-                let target_monkey_index = if worry_level % divisible_by == 0 {
-                    monkey_if_true
-                } else {
-                    monkey_if_false
-                };
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.round >= self.rounds {
+            return None;
+        }
 
-                monkeys[target_monkey_index].items.push(worry_level)
+        match self.play_round() {
+            Ok(()) => Some(Ok(self.snapshot())),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
             }
         }
     }
+}
 
-    monkeys.sort_unstable_by_key(|monkey| monkey.total_inspections);
+fn greatest_common_divisor(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
 
-    monkeys
-        .into_iter()
-        .rev()
-        .take(2)
-        .map(|monkey| monkey.total_inspections)
-        .product()
+/// The smallest number divisible by all of the divisors, or `None` if it doesn't fit in a `u64`.
+pub fn lowest_common_multiple(divisors: impl IntoIterator<Item = u64>) -> Option<u64> {
+    divisors.into_iter().try_fold(1, |lcm, divisor| {
+        (lcm / greatest_common_divisor(lcm, divisor)).checked_mul(divisor)
+    })
+}
+
+pub fn part1(input: &str) -> usize {
+    Simulation::new(input, Divide, 20)
+        .unwrap()
+        .run()
+        .unwrap()
+        .monkey_business()
+}
+
+pub fn part2(input: &str) -> usize {
+    let divisors = iter_monkeys(input).map(|monkey| monkey.test.divisible_by);
+    let modulus = lowest_common_multiple(divisors).unwrap();
+
+    Simulation::new(input, Modulo(modulus), 10_000)
+        .unwrap()
+        .run()
+        .unwrap()
+        .monkey_business()
 }
 
 #[cfg(test)]
@@ -220,4 +421,298 @@ mod tests {
         assert_eq!(part2(INPUT_TEST), 2_713_310_158);
         assert_eq!(part2(INPUT), 17_673_687_232);
     }
+
+    #[test]
+    fn simulation_works() {
+        let mut simulation = Simulation::new(INPUT_TEST, Divide, 20).unwrap();
+
+        let snapshot = simulation.next().unwrap().unwrap();
+        assert_eq!(snapshot.round, 1);
+        assert_eq!(
+            snapshot.items,
+            [
+                vec![20, 23, 27, 26],
+                vec![2080, 25, 167, 207, 401, 1046],
+                vec![],
+                vec![],
+            ]
+        );
+
+        let snapshots = simulation.map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(snapshots.len(), 19);
+        assert_eq!(snapshots[18].inspections, [101, 95, 7, 105]);
+
+        let snapshots = Simulation::new(INPUT_TEST, Modulo(96_577), 20)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert_eq!(snapshots[0].inspections, [2, 4, 3, 6]);
+        assert_eq!(snapshots[19].inspections, [99, 97, 8, 103]);
+    }
+
+    #[test]
+    fn expressions_work() {
+        let expr = |s: &str| s.parse::<Expr>();
+
+        assert_eq!(expr("old * old").unwrap().eval(7), Ok(49));
+        assert_eq!(expr("old + old").unwrap().eval(7), Ok(14));
+        assert_eq!(expr("old * 2 + 3").unwrap().eval(7), Ok(17));
+        assert_eq!(expr("3 + old * 2").unwrap().eval(7), Ok(17));
+        assert_eq!(expr("(old + 1) * (old - 1) / 4").unwrap().eval(7), Ok(12));
+        assert_eq!(expr("10 - 4 - 3").unwrap().eval(0), Ok(3));
+
+        assert_eq!(
+            expr("old * old").unwrap().eval(1 << 64),
+            Err(EvalError::Overflow)
+        );
+        assert_eq!(expr("old - 8").unwrap().eval(7), Err(EvalError::Underflow));
+        assert_eq!(
+            expr("old / (old - 7)").unwrap().eval(7),
+            Err(EvalError::DivisionByZero)
+        );
+
+        for invalid in ["", "old +", "new", "(old", "old old", "2old", "old ^ 2"] {
+            assert_eq!(expr(invalid), Err(()), "{invalid}");
+        }
+
+        let input = INPUT_TEST.replace("new = old * 19", "new = old * 2 + (old + 3) * 3");
+        let snapshot = Simulation::new(&input, Divide, 1).unwrap().run().unwrap();
+        assert_eq!(snapshot.inspections, [2, 4, 3, 5]);
+
+        let input = INPUT_TEST.replace("new = old + 3", "new = old * old * old");
+        let mut simulation = Simulation::new(&input, Divide, 20).unwrap();
+        let error = simulation.find_map(Result::err).unwrap();
+        assert_eq!(error.monkey, 3);
+        assert_eq!(simulation.next(), None);
+    }
+
+    #[test]
+    fn modular_operations_work() {
+        let modulus = 23 * 19 * 13 * 17;
+
+        for operation in [
+            "old + 7",
+            "old * 5",
+            "(old + 2) * (old + 1)",
+            "3 * (old + old) + 1",
+        ] {
+            let input = INPUT_TEST.replace("old * 19", operation);
+            assert!(operation.parse::<Expr>().unwrap().is_polynomial());
+
+            let by_modulus = Simulation::new(&input, Modulo(modulus), 1000).unwrap();
+            let exact = Simulation::new(&input, Exact, 1000).unwrap();
+            let mut rounds = 0;
+
+            for (a, b) in by_modulus
+                .map(Result::unwrap)
+                .zip(exact.map_while(Result::ok))
+            {
+                assert_eq!(a.inspections, b.inspections, "{operation}");
+                rounds += 1;
+            }
+
+            assert!(rounds > 0);
+        }
+
+        for operation in ["old - 1", "old / 2 + 7", "(old + 1) * (old - 1)"] {
+            let input = INPUT_TEST.replace("old * 19", operation);
+            assert!(!operation.parse::<Expr>().unwrap().is_polynomial());
+
+            let error = Simulation::new(&input, Modulo(modulus), 20).unwrap_err();
+            assert_eq!(error, Unsupported { monkey: 0 });
+            assert!(Simulation::new(&input, Divide, 20).is_ok());
+            assert!(Simulation::new(&input, Exact, 20).is_ok());
+        }
+
+        let input = INPUT_TEST.replace("old * 19", "old - 80");
+        let mut simulation = Simulation::new(&input, Divide, 20).unwrap();
+        let error = simulation.find_map(Result::err).unwrap();
+        assert_eq!((error.monkey, error.item), (0, 79));
+        assert_eq!(error.cause, EvalError::Underflow);
+
+        let input = INPUT_TEST.replace("old * 19", "old / (old - 79)");
+        let mut simulation = Simulation::new(&input, Divide, 20).unwrap();
+        let error = simulation.find_map(Result::err).unwrap();
+        assert_eq!(error.cause, EvalError::DivisionByZero);
+    }
+
+    // Generates monkeys whose divisors share factors
+    fn generate(seed: u64, monkeys: usize) -> String {
+        let mut state = seed;
+        let mut random = |n: u64| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1);
+            (state >> 33) % n
+        };
+
+        let divisors = [4, 6, 8, 9, 10, 12, 14, 15, 18, 20, 21, 25];
+        let mut chunks = Vec::new();
+
+        for monkey in 0..monkeys {
+            let items = (0..1 + random(4))
+                .map(|_| (1 + random(99)).to_string())
+                .collect::<Vec<_>>();
+            let operation = match random(5) {
+                0 => format!("old + {}", 1 + random(9)),
+                1 => format!("old * {}", 2 + random(18)),
+                2 => "old * old".to_owned(),
+                3 => "old + old".to_owned(),
+                _ => format!("old * 2 + {}", random(10)),
+            };
+            let divisor = divisors[random(divisors.len() as u64) as usize];
+            let if_true = (monkey + 1 + random(monkeys as u64 - 1) as usize) % monkeys;
+            let if_false = (monkey + 1 + random(monkeys as u64 - 1) as usize) % monkeys;
+
+            chunks.push(format!(
+                concat!(
+                    "Monkey {}:\n",
+                    "  Starting items: {}\n",
+                    "  Operation: new = {}\n",
+                    "  Test: divisible by {}\n",
+                    "    If true: throw to monkey {}\n",
+                    "    If false: throw to monkey {}\n",
+                ),
+                monkey,
+                items.join(", "),
+                operation,
+                divisor,
+                if_true,
+                if_false,
+            ));
+        }
+
+        chunks.join("\n")
+    }
+
+    // Keeps worry levels as they are, for as long as they fit
+    struct Exact;
+
+    impl Relief for Exact {
+        fn relieve(&self, worry_level: u128) -> u128 {
+            worry_level
+        }
+    }
+
+    #[test]
+    fn shared_factors_work() {
+        assert_eq!(lowest_common_multiple([4, 6, 10]), Some(60));
+        assert_eq!(lowest_common_multiple([]), Some(1));
+        assert_eq!(lowest_common_multiple([u64::MAX, 2]), None);
+
+        for seed in 0..10 {
+            let input = generate(seed, 6);
+
+            let divisors = iter_monkeys(&input)
+                .map(|monkey| monkey.test.divisible_by)
+                .collect::<Vec<_>>();
+            let lcm = lowest_common_multiple(divisors.iter().copied()).unwrap();
+            let product = divisors.iter().product::<u64>();
+            assert!(lcm < product);
+
+            let by_lcm = Simulation::new(&input, Modulo(lcm), 1000)
+                .unwrap()
+                .map(Result::unwrap);
+            let by_product = Simulation::new(&input, Modulo(product), 1000)
+                .unwrap()
+                .map(Result::unwrap);
+
+            for (a, b) in by_lcm.zip(by_product) {
+                assert_eq!(a.inspections, b.inspections);
+            }
+
+            let by_lcm = Simulation::new(&input, Modulo(lcm), 1000)
+                .unwrap()
+                .map(Result::unwrap);
+            let exact = Simulation::new(&input, Exact, 1000)
+                .unwrap()
+                .map_while(Result::ok);
+            let mut rounds = 0;
+
+            for (a, b) in by_lcm.zip(exact) {
+                let reduced = b
+                    .items
+                    .iter()
+                    .map(|items| items.iter().map(|item| item % lcm));
+                let reduced = reduced.map(Iterator::collect).collect::<Vec<Vec<_>>>();
+                assert_eq!(a.items, reduced);
+                assert_eq!(a.inspections, b.inspections);
+                rounds += 1;
+            }
+
+            assert!(rounds > 0);
+
+            // Neither modulus carries subtraction or division over, so both reject them
+            for operation in ["old - 1", "old / 2 + 7"] {
+                let (head, tail) = input.rsplit_once("Operation: new = ").unwrap();
+                let (_, tail) = tail.split_once('\n').unwrap();
+                let input = format!("{head}Operation: new = {operation}\n{tail}");
+
+                let error = Unsupported { monkey: 5 };
+                assert_eq!(
+                    Simulation::new(&input, Modulo(lcm), 1000).err(),
+                    Some(error)
+                );
+                assert_eq!(
+                    Simulation::new(&input, Modulo(product), 1000).err(),
+                    Some(error)
+                );
+                assert!(Simulation::new(&input, Exact, 1000).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn fast_forward_works() {
+        for (case, input) in [INPUT_TEST.to_owned(), INPUT.to_owned()]
+            .into_iter()
+            .chain((0..10).map(|seed| generate(seed, 6)))
+            .enumerate()
+        {
+            let divisors = iter_monkeys(&input).map(|monkey| monkey.test.divisible_by);
+            let modulus = lowest_common_multiple(divisors).unwrap();
+
+            let mut simulation = Simulation::new(&input, Modulo(modulus), 1000).unwrap();
+            assert_eq!(
+                simulation.fast_forward(0).unwrap(),
+                [0].repeat(simulation.monkeys.len())
+            );
+
+            let snapshots = simulation.clone().map(Result::unwrap).collect::<Vec<_>>();
+            for rounds in [1, 2, 3, 20, 99, 500, 1000] {
+                let inspections = simulation.fast_forward(rounds).unwrap();
+                assert_eq!(
+                    inspections,
+                    snapshots[rounds - 1].inspections,
+                    "{case} {rounds}"
+                );
+            }
+
+            // Starting from the middle of a simulation
+            simulation.nth(299).unwrap().unwrap();
+            let inspections = simulation.fast_forward(700).unwrap();
+            assert_eq!(inspections, snapshots[999].inspections, "{case}");
+        }
+
+        let modulus =
+            lowest_common_multiple(iter_monkeys(INPUT).map(|monkey| monkey.test.divisible_by));
+        let simulation = Simulation::new(INPUT, Modulo(modulus.unwrap()), 0).unwrap();
+        let inspections = simulation.fast_forward(10_000).unwrap();
+        assert_eq!(monkey_business(&inspections), part2(INPUT));
+
+        let inspections = simulation.fast_forward(1_000_000_000_000).unwrap();
+        assert_eq!(
+            inspections,
+            [
+                6_313_181_199_059,
+                196_185_286_113,
+                2_191_587_193_426,
+                12_763_510_445_098,
+                7_692_836_058_030,
+                13_352_066_303_434,
+                7_003_008_628_571,
+                13_221_276_112_708,
+            ]
+        );
+    }
 }
@@ -1,22 +1,11 @@
-use std::{array, ops::RangeInclusive};
+use std::{collections::HashSet, ops::RangeInclusive};
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-type Point = [i32; 2];
+use crate::interval::{Interval, IntervalSet};
 
-fn bounds<T: Ord + Copy, const N: usize>(
-    points: impl IntoIterator<Item = [T; N]>,
-) -> Option<[RangeInclusive<T>; N]> {
-    points.into_iter().fold(None, |bounds, point| match bounds {
-        None => Some(point.map(|x| x..=x)),
-        Some(ranges) => Some(array::from_fn(|i| {
-            let x = unsafe { *point.get_unchecked(i) };
-            let range = unsafe { ranges.get_unchecked(i) };
-            x.min(*range.start())..=x.max(*range.end())
-        })),
-    })
-}
+type Point = [i32; 2];
 
 fn parse_input(input: &str) -> impl Iterator<Item = (Point, Point)> + '_ {
     lazy_static! {
@@ -43,23 +32,19 @@ fn manhattan_distance(point_a: Point, point_b: Point) -> i32 {
 }
 
 pub fn part1(input: &str, y: i32) -> usize {
-    let distances = parse_input(input)
-        .map(|(sensor, beacon)| (sensor, beacon, manhattan_distance(sensor, beacon)))
-        .collect::<Vec<_>>();
+    let mut covered = IntervalSet::new();
+    let mut beacons = HashSet::new();
 
-    let [x_range, _] = bounds(distances.iter().copied().flat_map(|([x, y], _, dist)| {
-        [[x - dist, y], [x + dist, y], [x, y - dist], [x, y + dist]]
-    }))
-    .unwrap();
+    for (sensor @ [sensor_x, sensor_y], beacon @ [beacon_x, beacon_y]) in parse_input(input) {
+        let reach = manhattan_distance(sensor, beacon) - (sensor_y - y).abs();
+        covered.extend(Interval::new(sensor_x - reach, sensor_x + reach));
 
-    x_range
-        .filter(|&x| {
-            let point = [x, y];
-            distances.iter().any(|(sensor, beacon, dist)| {
-                point != *beacon && manhattan_distance(point, *sensor) <= *dist
-            })
-        })
-        .count()
+        if beacon_y == y {
+            beacons.insert(beacon_x);
+        }
+    }
+
+    covered.len() as usize - beacons.len()
 }
 
 pub fn part2(input: &str, range: RangeInclusive<i32>) -> i64 {
@@ -0,0 +1,303 @@
+use std::{
+    cmp::{max, min},
+    ops::RangeInclusive,
+    slice,
+};
+
+pub trait Integer: Copy + Ord {
+    fn checked_succ(self) -> Option<Self>;
+    fn checked_pred(self) -> Option<Self>;
+    fn distance(self, other: Self) -> u128;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                fn checked_succ(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn checked_pred(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+
+                fn distance(self, other: Self) -> u128 {
+                    (other as u128).wrapping_sub(self as u128)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// A non-empty, inclusive interval of integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Integer> Interval<T> {
+    pub fn new(start: T, end: T) -> Option<Self> {
+        (start <= end).then_some(Self { start, end })
+    }
+
+    pub fn point(value: T) -> Self {
+        Self {
+            start: value,
+            end: value,
+        }
+    }
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    /// Number of integers in the interval, saturating at `u128::MAX`.
+    pub fn size(&self) -> u128 {
+        self.start.distance(self.end).saturating_add(1)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    pub fn contains_interval(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        Self::new(max(self.start, other.start), min(self.end, other.end))
+    }
+
+    /// Returns the smallest interval covering both, if they overlap or are adjacent.
+    pub fn union(&self, other: &Self) -> Option<Self> {
+        if self.precedes(other) || other.precedes(self) {
+            None
+        } else {
+            Some(Self {
+                start: min(self.start, other.start),
+                end: max(self.end, other.end),
+            })
+        }
+    }
+
+    /// Whether `self` lies entirely before `other`, with at least one integer between them.
+    fn precedes(&self, other: &Self) -> bool {
+        self.end < other.start && self.end.checked_succ().unwrap() < other.start
+    }
+}
+
+impl<T: Integer> From<Interval<T>> for RangeInclusive<T> {
+    fn from(interval: Interval<T>) -> Self {
+        interval.start..=interval.end
+    }
+}
+
+impl<T: Integer> TryFrom<RangeInclusive<T>> for Interval<T> {
+    type Error = ();
+
+    fn try_from(range: RangeInclusive<T>) -> Result<Self, Self::Error> {
+        let (start, end) = range.into_inner();
+        Self::new(start, end).ok_or(())
+    }
+}
+
+/// A set of integers, stored as sorted, disjoint and non-adjacent intervals.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self {
+            intervals: Vec::new(),
+        }
+    }
+}
+
+impl<T: Integer> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Number of integers in the set, saturating at `u128::MAX`.
+    pub fn len(&self) -> u128 {
+        self.intervals
+            .iter()
+            .fold(0u128, |len, interval| len.saturating_add(interval.size()))
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Interval<T>> {
+        self.intervals.iter()
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let index = self
+            .intervals
+            .partition_point(|interval| interval.end < value);
+        matches!(self.intervals.get(index), Some(interval) if interval.contains(value))
+    }
+
+    pub fn insert(&mut self, interval: Interval<T>) {
+        let lo = self
+            .intervals
+            .partition_point(|other| other.precedes(&interval));
+        let hi = self
+            .intervals
+            .partition_point(|other| !interval.precedes(other));
+
+        let merged = self.intervals[lo..hi]
+            .iter()
+            .fold(interval, |merged, other| merged.union(other).unwrap());
+
+        self.intervals.splice(lo..hi, [merged]);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut set = self.clone();
+        set.extend(other.iter().copied());
+        set
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intervals = Vec::new();
+        let (mut a, mut b) = (self.iter().peekable(), other.iter().peekable());
+
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            intervals.extend(x.intersection(y));
+            if x.end < y.end {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+
+        Self { intervals }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut intervals = Vec::new();
+        let mut others = other.iter().peekable();
+
+        for interval in self.iter() {
+            let mut rest = Some(*interval);
+
+            while let (Some(current), Some(other)) = (rest, others.peek()) {
+                if other.end < current.start {
+                    others.next();
+                    continue;
+                }
+                if current.end < other.start {
+                    break;
+                }
+                if current.start < other.start {
+                    intervals.push(Interval {
+                        start: current.start,
+                        end: other.start.checked_pred().unwrap(),
+                    });
+                }
+                rest = other
+                    .end
+                    .checked_succ()
+                    .and_then(|start| Interval::new(start, current.end));
+                if other.end <= current.end {
+                    others.next();
+                }
+            }
+
+            intervals.extend(rest);
+        }
+
+        Self { intervals }
+    }
+}
+
+impl<T: Integer> Extend<Interval<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = Interval<T>>>(&mut self, iter: I) {
+        for interval in iter {
+            self.insert(interval);
+        }
+    }
+}
+
+impl<T: Integer> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a, T> IntoIterator for &'a IntervalSet<T> {
+    type Item = &'a Interval<T>;
+    type IntoIter = slice::Iter<'a, Interval<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.intervals.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set<T: Integer>(ranges: impl IntoIterator<Item = RangeInclusive<T>>) -> IntervalSet<T> {
+        ranges
+            .into_iter()
+            .map(|range| range.try_into().unwrap())
+            .collect()
+    }
+
+    fn ranges<T: Integer>(set: &IntervalSet<T>) -> Vec<RangeInclusive<T>> {
+        set.iter().map(|&interval| interval.into()).collect()
+    }
+
+    #[test]
+    fn interval_works() {
+        let a = Interval::new(2u8, 8).unwrap();
+        let b = Interval::new(3u8, 7).unwrap();
+        let c = Interval::new(9u8, 9).unwrap();
+
+        assert!(Interval::new(3u8, 2).is_none());
+        assert!(a.contains_interval(&b) && !b.contains_interval(&a));
+        assert!(a.overlaps(&b) && !a.overlaps(&c));
+        assert_eq!(a.union(&c), Interval::new(2, 9));
+        assert_eq!(b.union(&c), None);
+        assert_eq!(a.intersection(&c), None);
+        assert_eq!(Interval::new(i8::MIN, i8::MAX).unwrap().size(), 256);
+        assert_eq!(Interval::new(0, u128::MAX).unwrap().size(), u128::MAX);
+    }
+
+    #[test]
+    fn set_works() {
+        let a = set([1..=3, 10..=12, 5..=6, 4..=4, 20..=30]);
+        let b = set([0..=1, 6..=11, 25..=i64::MAX]);
+
+        assert_eq!(ranges(&a), [1..=6, 10..=12, 20..=30]);
+        assert_eq!(a.len(), 6 + 3 + 11);
+        assert!(a.contains(5) && !a.contains(7));
+        assert_eq!(ranges(&a.union(&b)), [0..=12, 20..=i64::MAX]);
+        assert_eq!(
+            ranges(&a.intersection(&b)),
+            [1..=1, 6..=6, 10..=11, 25..=30]
+        );
+        assert_eq!(ranges(&a.difference(&b)), [2..=5, 12..=12, 20..=24]);
+        assert_eq!(ranges(&b.difference(&a)), [0..=0, 7..=9, 31..=i64::MAX]);
+    }
+}
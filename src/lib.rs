@@ -13,3 +13,5 @@ pub mod day12;
 pub mod day13;
 pub mod day14;
 pub mod day15;
+pub mod interval;
+pub mod ocr;
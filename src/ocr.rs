@@ -0,0 +1,108 @@
+//! Reads the letters drawn by puzzles, in the 4×6 font used throughout Advent of Code.
+
+const WIDTH: usize = 4;
+const HEIGHT: usize = 6;
+
+// Glyphs are separated by a blank column
+const SPACING: usize = WIDTH + 1;
+
+// `Y` is left out, as it's the only letter drawn five pixels wide
+static GLYPHS: [(char, [&str; HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The image isn't six rows of `#` and `.` of equal width, fitting a whole number of glyphs.
+    Malformed,
+    /// The glyph at the given index isn't a known letter.
+    UnknownGlyph(usize),
+}
+
+// Packs the pixels of a glyph into the lowest 24 bits, row by row
+fn encode<'a>(rows: impl IntoIterator<Item = &'a [u8]>) -> u32 {
+    rows.into_iter()
+        .flatten()
+        .fold(0, |bits, &pixel| bits << 1 | (pixel == b'#') as u32)
+}
+
+/// Reads the letters in an image of `#` and `.` pixels.
+pub fn recognize(image: &str) -> Result<String, Error> {
+    let rows = image.lines().map(str::as_bytes).collect::<Vec<_>>();
+
+    let width = rows.first().map_or(0, |row| row.len());
+
+    let well_formed = rows.len() == HEIGHT
+        && (width % SPACING == 0 || (width + 1) % SPACING == 0)
+        && rows.iter().all(|row| row.len() == width)
+        && rows
+            .iter()
+            .flat_map(|row| row.iter())
+            .all(|pixel| b"#.".contains(pixel));
+
+    if !well_formed {
+        return Err(Error::Malformed);
+    }
+
+    (0..(width + 1) / SPACING)
+        .map(|index| {
+            let x = index * SPACING;
+            let glyph = encode(rows.iter().map(|row| &row[x..x + WIDTH]));
+
+            GLYPHS
+                .iter()
+                .find(|(_, pixels)| encode(pixels.map(str::as_bytes)) == glyph)
+                .map(|&(letter, _)| letter)
+                .ok_or(Error::UnknownGlyph(index))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognize_works() {
+        let image = concat!(
+            ".##..###...##..####.####..##..#..#..###.\n",
+            "#..#.#..#.#..#.#....#....#..#.#..#...#..\n",
+            "#..#.###..#....###..###..#....####...#..\n",
+            "####.#..#.#....#....#....#.##.#..#...#..\n",
+            "#..#.#..#.#..#.#....#....#..#.#..#...#..\n",
+            "#..#.###...##..####.#.....###.#..#..###.\n",
+        );
+        assert_eq!(recognize(image), Ok("ABCEFGHI".into()));
+        assert_eq!(
+            recognize(&image.replace(".\n", "\n")),
+            Ok("ABCEFGHI".into())
+        );
+
+        let mut image = image.to_owned();
+        image.replace_range(18..19, ".");
+        assert_eq!(recognize(&image), Err(Error::UnknownGlyph(3)));
+
+        assert_eq!(recognize(&image[1..]), Err(Error::Malformed));
+        assert_eq!(recognize(&image.replace('.', " ")), Err(Error::Malformed));
+        assert_eq!(recognize(""), Err(Error::Malformed));
+
+        let cut_off_y = "#...\n#...\n.#.#\n..#.\n..#.\n..#.\n";
+        assert_eq!(recognize(cut_off_y), Err(Error::UnknownGlyph(0)));
+    }
+}